@@ -6,9 +6,8 @@ use vpp_api::memclnt;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::connect_unix("/var/run/vpp/api.sock").await?;
-    let ctx = client.send_msg(memclnt::ApiVersions::new()).await?;
-    let rep = client.recv_msg::<memclnt::ApiVersionsReply>(ctx).await?;
+    let client = Client::builder().name("rsvpp-version").connect().await?;
+    let rep = client.call(memclnt::ApiVersions::new()).await?;
 
     println!("Versions: {:#?}", rep);
 
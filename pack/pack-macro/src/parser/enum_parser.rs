@@ -191,7 +191,7 @@ impl EnumParser {
             }
         } else {
             quote! {
-                _ => return Err(format!("Enum Pack received invalid number: '{}', type: '{}'", v, std::any::type_name::<Self>()).into()),
+                _ => return Err(pack::Error::invalid_enum_value(v as u64, std::any::type_name::<Self>())),
             }
         };
 
@@ -215,22 +215,34 @@ impl StructParser {
             v
         });
 
+        let struct_name = &self.name;
         let toks: Vec<TokenStream> = self
             .fields
             .iter()
             .map(|field| {
                 let name = str_to_toks(&field.name);
+                let field_name = &field.name;
                 let ty = str_to_toks(&field.ty);
                 if let Some(LengthInfo::Fixed(len)) = field.length {
                     quote! {
                         offset = pack::align_offset(offset, <#ty>::align_size(), #packed);
-                        self.#name.pack(pack::safe_slice_mut(buf, offset, Some(self.#name.size()))?)?;
+                        let size = self.#name.size();
+                        if size > #len * <#ty>::align_size() {
+                            return Err(pack::Error::length_mismatch(#len * <#ty>::align_size(), size)
+                                .in_field(#struct_name, #field_name, offset));
+                        }
+                        pack::safe_slice_mut(buf, offset, Some(size))
+                            .and_then(|buf| self.#name.pack(buf))
+                            .map_err(|e| e.in_field(#struct_name, #field_name, offset))?;
                         offset += #len;
                     }
                 } else {
                     quote! {
                         offset = pack::align_offset(offset, <#ty>::align_size(), #packed);
-                        offset += self.#name.pack(pack::safe_slice_mut(buf, offset, Some(self.#name.size()))?)?;
+                        let size = self.#name.size();
+                        offset += pack::safe_slice_mut(buf, offset, Some(size))
+                            .and_then(|buf| self.#name.pack(buf))
+                            .map_err(|e| e.in_field(#struct_name, #field_name, offset))?;
                     }
                 }
             })
@@ -249,11 +261,13 @@ impl StructParser {
 
     fn gen_unpack_fn_body(&self) -> TokenStream {
         let packed = self.packed;
+        let struct_name = &self.name;
         let toks: Vec<TokenStream> = self
             .fields
             .iter()
             .map(|field| {
                 let name = format_ident!("__{}__", field.name);
+                let field_name = &field.name;
                 let ty = str_to_toks(&field.ty);
 
                 let len = if let Some(LengthInfo::Refer(r)) = &field.length {
@@ -271,7 +285,9 @@ impl StructParser {
 
                 quote! {
                     offset = pack::align_offset(offset, <#ty>::align_size(), #packed);
-                    let res = <#ty>::unpack(pack::safe_slice(&buf, offset, None)?, #len)?;
+                    let res = pack::safe_slice(&buf, offset, None)
+                        .and_then(|buf| <#ty>::unpack(buf, #len))
+                        .map_err(|e| e.in_field(#struct_name, #field_name, offset))?;
                     let #name = res.0;
                     offset += #size;
                 }
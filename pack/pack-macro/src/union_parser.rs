@@ -154,7 +154,7 @@ impl UnionParser {
     fn gen_pack_fn_body(&self) -> TokenStream {
         quote! {
             if buf.len() < self.buf.len() {
-                return Err("Buffer not enough".into());
+                return Err(pack::Error::buffer_too_short(self.buf.len(), buf.len()));
             }
 
             self.buf.iter().enumerate().for_each(|(i, v)| buf[i] = *v);
@@ -167,7 +167,7 @@ impl UnionParser {
         quote! {
             let len = Self::static_size();
             if buf.len() < len {
-                return Err("Buffer not enough".into());
+                return Err(pack::Error::buffer_too_short(len, buf.len()));
             }
 
             Ok((
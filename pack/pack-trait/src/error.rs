@@ -0,0 +1,194 @@
+use std::fmt;
+
+/// Where a pack error occurred, filled in by `#[derive(Pack)]` while the error bubbles up
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Outermost struct, e.g. `AclAddReplace`
+    pub ty: Option<&'static str>,
+    /// Field names and array indexes from `ty` to the failed value, e.g. `r`, `[3]`, `src_prefix`
+    pub segments: Vec<String>,
+    /// Byte offset from the start of `ty`
+    pub offset: usize,
+}
+
+impl Location {
+    /// Path like `AclAddReplace.r[3].src_prefix.len`
+    pub fn path(&self) -> String {
+        let mut path = self.ty.unwrap_or_default().to_string();
+        for seg in &self.segments {
+            if !path.is_empty() && !seg.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(seg);
+        }
+
+        path
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    BufferTooShort {
+        at: Location,
+        expect: usize,
+        remain: usize,
+    },
+    InvalidEnumValue {
+        at: Location,
+        value: u64,
+        ty: &'static str,
+    },
+    /// String without `\0` terminator
+    MissingNul {
+        at: Location,
+    },
+    Utf8 {
+        at: Location,
+        source: std::string::FromUtf8Error,
+    },
+    /// Value doesn't fit its fixed length field
+    LengthMismatch {
+        at: Location,
+        expect: usize,
+        actual: usize,
+    },
+    Other {
+        at: Location,
+        msg: String,
+    },
+}
+
+impl Error {
+    pub fn buffer_too_short(expect: usize, remain: usize) -> Self {
+        Self::BufferTooShort {
+            at: Location::default(),
+            expect,
+            remain,
+        }
+    }
+
+    pub fn invalid_enum_value(value: u64, ty: &'static str) -> Self {
+        Self::InvalidEnumValue {
+            at: Location::default(),
+            value,
+            ty,
+        }
+    }
+
+    pub fn length_mismatch(expect: usize, actual: usize) -> Self {
+        Self::LengthMismatch {
+            at: Location::default(),
+            expect,
+            actual,
+        }
+    }
+
+    pub fn location(&self) -> &Location {
+        match self {
+            Self::BufferTooShort { at, .. }
+            | Self::InvalidEnumValue { at, .. }
+            | Self::MissingNul { at }
+            | Self::Utf8 { at, .. }
+            | Self::LengthMismatch { at, .. }
+            | Self::Other { at, .. } => at,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Location {
+        match self {
+            Self::BufferTooShort { at, .. }
+            | Self::InvalidEnumValue { at, .. }
+            | Self::MissingNul { at }
+            | Self::Utf8 { at, .. }
+            | Self::LengthMismatch { at, .. }
+            | Self::Other { at, .. } => at,
+        }
+    }
+
+    pub fn path(&self) -> String {
+        self.location().path()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.location().offset
+    }
+
+    /// Error of field `name` at `offset` of struct `ty`
+    pub fn in_field(mut self, ty: &'static str, name: &str, offset: usize) -> Self {
+        let at = self.location_mut();
+        at.ty = Some(ty);
+        at.segments.insert(0, name.to_string());
+        at.offset += offset;
+        self
+    }
+
+    /// Error of array element `index` at `offset` of array
+    pub fn in_element(mut self, index: usize, offset: usize) -> Self {
+        let at = self.location_mut();
+        at.segments.insert(0, format!("[{}]", index));
+        at.offset += offset;
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferTooShort { expect, remain, .. } => write!(
+                f,
+                "Out of buffer, expect size {}, remain size: {}",
+                expect, remain
+            )?,
+            Self::InvalidEnumValue { value, ty, .. } => {
+                write!(f, "Invalid enum value '{}' of '{}'", value, ty)?
+            }
+            Self::MissingNul { .. } => write!(f, "\\0 Not found")?,
+            Self::Utf8 { source, .. } => write!(f, "Invalid utf8: {}", source)?,
+            Self::LengthMismatch { expect, actual, .. } => write!(
+                f,
+                "Length mismatch, expect at most {}, actual: {}",
+                expect, actual
+            )?,
+            Self::Other { msg, .. } => write!(f, "{}", msg)?,
+        }
+
+        let at = self.location();
+        if at.ty.is_some() || !at.segments.is_empty() {
+            write!(f, ", at '{}'", at.path())?;
+        }
+        write!(f, ", offset {}", at.offset)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Utf8 { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(msg: String) -> Self {
+        Self::Other {
+            at: Location::default(),
+            msg,
+        }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(msg: &str) -> Self {
+        Self::from(msg.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(source: std::string::FromUtf8Error) -> Self {
+        Self::Utf8 {
+            at: Location::default(),
+            source,
+        }
+    }
+}
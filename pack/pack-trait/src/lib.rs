@@ -1,5 +1,7 @@
+mod error;
 mod pack;
 mod pack_default;
 
+pub use error::*;
 pub use pack::*;
 pub use pack_default::*;
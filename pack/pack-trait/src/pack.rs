@@ -1,9 +1,8 @@
-use std::{
-    convert::TryInto,
-    mem::{size_of, MaybeUninit},
-};
+use std::mem::{size_of, MaybeUninit};
 
-pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+use crate::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
 
 pub trait Pack: Sized {
     /// Get pack size
@@ -46,13 +45,13 @@ pub fn align_offset(offset: usize, align: usize, skip: bool) -> usize {
 pub fn safe_slice<T>(slice: &[T], start: usize, n: Option<usize>) -> Result<&[T]> {
     if let Some(n) = n {
         if start + n > slice.len() {
-            return Err("Slice out of range".into());
+            return Err(Error::buffer_too_short(start + n, slice.len()));
         }
 
         Ok(&slice[start..start + n])
     } else {
         if start > slice.len() {
-            return Err("Slice out of range".into());
+            return Err(Error::buffer_too_short(start, slice.len()));
         }
 
         Ok(&slice[start..])
@@ -62,13 +61,13 @@ pub fn safe_slice<T>(slice: &[T], start: usize, n: Option<usize>) -> Result<&[T]
 pub fn safe_slice_mut<T>(slice: &mut [T], start: usize, n: Option<usize>) -> Result<&mut [T]> {
     if let Some(n) = n {
         if start + n > slice.len() {
-            return Err("Slice out of range".into());
+            return Err(Error::buffer_too_short(start + n, slice.len()));
         }
 
         Ok(&mut slice[start..start + n])
     } else {
         if start >= slice.len() {
-            return Err("Slice out of range".into());
+            return Err(Error::buffer_too_short(start + 1, slice.len()));
         }
 
         Ok(&mut slice[start..])
@@ -100,12 +99,7 @@ macro_rules! min {
 macro_rules! validate_buffer {
     ($buf:expr, $size:expr) => {
         if $buf.len() < $size {
-            return Err(format!(
-                "Out of buffer, expect size {}, remain size: {}",
-                $size,
-                $buf.len()
-            )
-            .into());
+            return Err(Error::buffer_too_short($size, $buf.len()));
         }
     };
 }
@@ -136,11 +130,10 @@ macro_rules! impl_pack_for_number {
 
             fn unpack(buf: &[u8], _: usize) -> Result<(Self, usize)> {
                 validate_buffer!(buf, Self::align_size());
+                let mut bytes = [0_u8; size_of::<$type>()];
+                bytes.copy_from_slice(&buf[0..Self::align_size()]);
 
-                Ok((
-                    <$type>::from_be_bytes((&buf[0..Self::align_size()]).try_into()?),
-                    Self::align_size(),
-                ))
+                Ok((<$type>::from_be_bytes(bytes), Self::align_size()))
             }
         }
     };
@@ -223,7 +216,9 @@ impl Pack for String {
         }
 
         if buf.len() == s_buf.len() {
-            return Err("\\0 Not found".into());
+            return Err(Error::MissingNul {
+                at: Default::default(),
+            });
         }
 
         let s = String::from_utf8(s_buf)?;
@@ -250,7 +245,9 @@ impl<T: Pack, const N: usize> Pack for [T; N] {
         let mut offset = 0;
         for i in 0..N {
             validate_buffer!(buf, offset);
-            offset += self[i].pack(&mut buf[offset..])?;
+            offset += self[i]
+                .pack(&mut buf[offset..])
+                .map_err(|e| e.in_element(i, offset))?;
         }
 
         Ok(offset)
@@ -261,7 +258,7 @@ impl<T: Pack, const N: usize> Pack for [T; N] {
         let mut offset = 0;
         for i in 0..N {
             validate_buffer!(buf, offset);
-            let res = T::unpack(&buf[offset..], 0)?;
+            let res = T::unpack(&buf[offset..], 0).map_err(|e| e.in_element(i, offset))?;
             arr[i] = res.0;
             offset += res.1;
         }
@@ -285,9 +282,11 @@ impl<T: Pack> Pack for Vec<T> {
 
     fn pack(&mut self, buf: &mut [u8]) -> Result<usize> {
         let mut offset = 0;
-        for v in self {
+        for (i, v) in self.iter_mut().enumerate() {
             validate_buffer!(buf, offset);
-            offset += v.pack(&mut buf[offset..])?;
+            offset += v
+                .pack(&mut buf[offset..])
+                .map_err(|e| e.in_element(i, offset))?;
         }
 
         Ok(offset)
@@ -296,9 +295,9 @@ impl<T: Pack> Pack for Vec<T> {
     fn unpack(buf: &[u8], len: usize) -> Result<(Self, usize)> {
         let mut arr: Vec<T> = Vec::new();
         let mut offset = 0;
-        for _ in 0..len {
+        for i in 0..len {
             validate_buffer!(buf, offset);
-            let res = T::unpack(&buf[offset..], 0)?;
+            let res = T::unpack(&buf[offset..], 0).map_err(|e| e.in_element(i, offset))?;
             arr.push(res.0);
             offset += res.1;
         }
@@ -308,3 +308,49 @@ fn test6() {
     assert_eq!(A::unpack(&v, 0).unwrap().0, a);
     assert_eq!(a.ipv6(), [1, 2, 3, 4]);
 }
+
+#[test]
+fn error_path() {
+    #[derive(Pack, Debug, PartialEq, Eq)]
+    #[packed]
+    struct Prefix {
+        addr: u32,
+        len: u8,
+    }
+
+    #[derive(Pack, Debug, PartialEq, Eq)]
+    #[packed]
+    struct Rule {
+        action: u8,
+        src_prefix: Prefix,
+    }
+
+    #[derive(Pack, Debug, PartialEq, Eq)]
+    #[packed]
+    struct AclAddReplace {
+        count: u32,
+        #[len("count")]
+        r: Vec<Rule>,
+    }
+
+    #[derive(Pack, Debug, PartialEq, Eq)]
+    struct Tag {
+        #[len(2)]
+        tag: String,
+    }
+
+    // Last byte of r[1] is missing
+    let v = vec![0, 0, 0, 2, 1, 0, 0, 0, 1, 24, 2, 0, 0, 0, 2];
+    let e = AclAddReplace::unpack(&v, 0).unwrap_err();
+    assert!(matches!(e, pack::Error::BufferTooShort { .. }));
+    assert_eq!(e.path(), "AclAddReplace.r[1].src_prefix.len");
+    assert_eq!(e.offset(), 15);
+
+    let e = Tag {
+        tag: "abc".to_string(),
+    }
+    .pack_vec()
+    .unwrap_err();
+    assert!(matches!(e, pack::Error::LengthMismatch { .. }));
+    assert_eq!(e.path(), "Tag.tag");
+}
@@ -36,10 +36,19 @@ macro_rules! ensure_not_duplicate {
     };
 }
 
+/// Entry of `foreach_vnet_api_error` in api_errno.h
+struct ApiErrno {
+    name: String,
+    code: i32,
+    msg: String,
+}
+
 pub struct Generator {
     in_dir: String,
     out_dir: String,
     error_header_file: String,
+    blocking: bool,
+    idempotent: HashSet<String>,
 }
 
 impl Generator {
@@ -48,9 +57,23 @@ impl Generator {
             in_dir: in_dir.to_string(),
             out_dir: out_dir.to_string(),
             error_header_file: error_header_file.to_string(),
+            blocking: false,
+            idempotent: HashSet::new(),
         })
     }
 
+    /// Generate services on `rsvpp::blocking::Client`, which needs the `blocking` feature of rsvpp
+    pub fn set_blocking(&mut self, blocking: bool) {
+        self.blocking = blocking;
+    }
+
+    /// Mark request `name` idempotent, so it's retried by the client retry policy
+    ///
+    /// Requests named like `show_*`, `*_get` and `*_dump` are idempotent without this.
+    pub fn add_idempotent<S: ToString>(&mut self, name: S) {
+        self.idempotent.insert(name.to_string());
+    }
+
     pub fn gen(&mut self) -> Result<()> {
         // Parse api
         let paths = glob::glob(&format!("{}/**/*.api.json", self.in_dir))?;
@@ -65,7 +88,12 @@ impl Generator {
         // Generate api
         for api in apis {
             println!("Generate rust code from api: '{}'", api.name);
-            Self::gen_single_api(&api, &format!("{}/{}.rs", self.out_dir, api.name))?;
+            Self::gen_single_api(
+                &api,
+                &format!("{}/{}.rs", self.out_dir, api.name),
+                self.blocking,
+                &self.idempotent,
+            )?;
         }
 
         // Parse error
@@ -91,16 +119,18 @@ impl Generator {
         Ok(api)
     }
 
-    fn parse_error_header_file(file: &str) -> Result<Vec<(i32, String)>> {
+    fn parse_error_header_file(file: &str) -> Result<Vec<ApiErrno>> {
         let buf = std::fs::read(file).map_err(|e| format!("Read '{}' error: {}", file, e))?;
         let content = std::str::from_utf8(&buf)?;
-        let err_regex = regex::Regex::new(r#"_\([A-Z_0-9a-z]+,\s*(\-?\d+),\s*"(.*)""#)?;
-        let mut arr: Vec<(i32, String)> = Vec::new();
+        let err_regex = regex::Regex::new(r#"_\(([A-Z_0-9a-z]+),\s*(\-?\d+),\s*"(.*)""#)?;
+        let mut arr: Vec<ApiErrno> = Vec::new();
         for line in content.split("\n") {
             if let Some(cap) = err_regex.captures(line) {
-                let err_code: i32 = cap[1].parse()?;
-                let err_msg: String = cap[2].to_string();
-                arr.push((err_code, err_msg));
+                arr.push(ApiErrno {
+                    name: cap[1].to_string(),
+                    code: cap[2].parse()?,
+                    msg: cap[3].to_string(),
+                });
             }
         }
 
@@ -108,7 +138,7 @@ impl Generator {
     }
 
     #[rustfmt::skip]
-    fn gen_single_api(api: &JsonApi, file: &str) -> Result<()> {
+    fn gen_single_api(api: &JsonApi, file: &str, blocking: bool, idempotent: &HashSet<String>) -> Result<()> {
         let mut lines: Vec<String> = Vec::new();
         let mut generated_type_set: HashSet<String> = HashSet::new();
         let has_retval_type_set: HashSet<String> = Self::get_has_retval_has_set(api)?;
@@ -119,13 +149,10 @@ impl Generator {
         lines.push(format!("use rsvpp::pack::{{self, Pack, PackDefault, pack_union}};\n"));
 
         // Gen check error function
-        lines.push(format!("fn check_error(retval: i32) -> rsvpp::Result<()> {{"));
+        lines.push(format!("fn check_error(retval: i32, message_name: &str) -> rsvpp::Result<()> {{"));
         lines.push(format!("    if retval != 0 {{"));
-        lines.push(format!("        if let Some(msg) = super::error_map::ERROR_MAP.get(&retval) {{"));
-        lines.push(format!("            return Err(rsvpp::Error::vpp_api(format!(\"code: {{}}, msg: '{{}}'\", retval, msg)));"));
-        lines.push(format!("        }} else {{"));
-        lines.push(format!("            return Err(rsvpp::Error::vpp_api(format!(\"code: {{}}, msg: NULL\", retval)));"));
-        lines.push(format!("        }}"));
+        lines.push(format!("        let errno = super::error_map::VppErrno::from_code(retval).map(Into::into);"));
+        lines.push(format!("        return Err(rsvpp::Error::vpp_api(retval, errno, message_name));"));
         lines.push(format!("    }}"));
         lines.push(format!("    Ok(())"));
         lines.push(format!("}}\n"));
@@ -145,6 +172,9 @@ impl Generator {
             lines.extend(Self::gen_message(msg, &mut generated_type_set)?);
         }
 
+        // Gen message list
+        lines.extend(Self::gen_message_descs(&api.messages)?);
+
         // Gen unions
         for uni in &api.unions {
             lines.extend(Self::gen_union(uni, &mut generated_type_set)?);
@@ -160,13 +190,32 @@ impl Generator {
             lines.extend(Self::gen_enum(enu, &mut generated_type_set)?);
         }
 
-        // Gen services
-        lines.extend(Self::gen_services(
-            &api.name,
+        // Gen requests
+        let request_set = Self::get_request_set(api);
+        lines.extend(Self::gen_requests(
             &api.services,
+            &request_set,
             &has_retval_type_set,
+            idempotent,
         )?);
 
+        // Gen services
+        if blocking {
+            lines.extend(Self::gen_blocking_services(
+                &api.name,
+                &api.services,
+                &has_retval_type_set,
+                &request_set,
+            )?);
+        } else {
+            lines.extend(Self::gen_services(
+                &api.name,
+                &api.services,
+                &has_retval_type_set,
+                &request_set,
+            )?);
+        }
+
         // Join code
         let code = lines.join("\n");
 
@@ -194,22 +243,105 @@ impl Generator {
         Ok(set)
     }
 
-    fn gen_error_map(errs: Vec<(i32, String)>, outdir: &str) -> Result<()> {
+    fn get_request_set(api: &JsonApi) -> HashSet<String> {
+        let has_fields = |name: &String, fields: &[&str]| {
+            api.messages
+                .iter()
+                .find(|msg| &msg.name == name)
+                .map(|msg| {
+                    fields
+                        .iter()
+                        .all(|f| msg.fields.iter().any(|field| &field.name == f))
+                })
+                .unwrap_or(false)
+        };
+
+        api.services
+            .iter()
+            .filter(|service| {
+                has_fields(&service.req, &["_vl_msg_id", "client_index", "context"])
+                    && has_fields(&service.rep, &["_vl_msg_id", "context"])
+            })
+            .map(|service| service.req.clone())
+            .collect()
+    }
+
+    #[rustfmt::skip]
+    fn gen_error_map(errs: Vec<ApiErrno>, outdir: &str) -> Result<()> {
         let mut lines: Vec<String> = Vec::new();
 
         lines.push(format!("use std::collections::HashMap;\n"));
         lines.push(format!("rsvpp::lazy_static::lazy_static!("));
-        lines.push(format!(
-            "    pub static ref ERROR_MAP: HashMap<i32, &'static str> = {{"
-        ));
+        lines.push(format!("    pub static ref ERROR_MAP: HashMap<i32, &'static str> = {{"));
         lines.push(format!("        let mut m = HashMap::new();"));
-        for pair in errs {
-            lines.push(format!("        m.insert({}, \"{}\");", pair.0, pair.1));
+        for err in &errs {
+            lines.push(format!("        m.insert({}, \"{}\");", err.code, err.msg));
         }
         lines.push(format!("        m"));
         lines.push(format!("    }};"));
         lines.push(format!(");\n"));
 
+        // Codes are discriminants, keep the first entry of a duplicated code
+        let mut codes: HashSet<i32> = HashSet::new();
+        let errs = errs
+            .iter()
+            .filter(|err| codes.insert(err.code))
+            .map(|err| (gen_errno_variant(&err.name), err))
+            .collect::<Vec<_>>();
+
+        lines.push(format!("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]"));
+        lines.push(format!("#[repr(i32)]"));
+        lines.push(format!("pub enum VppErrno {{"));
+        for (variant, err) in &errs {
+            lines.push(format!("    {} = {},", variant, err.code));
+        }
+        lines.push(format!("}}\n"));
+
+        lines.push(format!("impl VppErrno {{"));
+        lines.push(format!("    pub fn from_code(code: i32) -> Option<Self> {{"));
+        lines.push(format!("        match code {{"));
+        for (variant, err) in &errs {
+            lines.push(format!("            {} => Some(Self::{}),", err.code, variant));
+        }
+        lines.push(format!("            _ => None,"));
+        lines.push(format!("        }}"));
+        lines.push(format!("    }}\n"));
+        lines.push(format!("    /// Errno of `rsvpp::Error::VppApi`"));
+        lines.push(format!("    pub fn of(err: &rsvpp::Error) -> Option<Self> {{"));
+        lines.push(format!("        match err {{"));
+        lines.push(format!("            rsvpp::Error::VppApi {{ code, .. }} => Self::from_code(*code),"));
+        lines.push(format!("            _ => None,"));
+        lines.push(format!("        }}"));
+        lines.push(format!("    }}\n"));
+        lines.push(format!("    pub fn code(&self) -> i32 {{"));
+        lines.push(format!("        *self as i32"));
+        lines.push(format!("    }}\n"));
+        lines.push(format!("    /// Symbolic name in api_errno.h, e.g. `NO_SUCH_ENTRY`"));
+        lines.push(format!("    pub fn name(&self) -> &'static str {{"));
+        lines.push(format!("        match self {{"));
+        for (variant, err) in &errs {
+            lines.push(format!("            Self::{} => \"{}\",", variant, err.name));
+        }
+        lines.push(format!("        }}"));
+        lines.push(format!("    }}\n"));
+        lines.push(format!("    pub fn message(&self) -> &'static str {{"));
+        lines.push(format!("        match self {{"));
+        for (variant, err) in &errs {
+            lines.push(format!("            Self::{} => \"{}\",", variant, err.msg));
+        }
+        lines.push(format!("        }}"));
+        lines.push(format!("    }}"));
+        lines.push(format!("}}\n"));
+
+        lines.push(format!("impl From<VppErrno> for rsvpp::VppErrno {{"));
+        lines.push(format!("    fn from(errno: VppErrno) -> Self {{"));
+        lines.push(format!("        Self {{"));
+        lines.push(format!("            name: errno.name(),"));
+        lines.push(format!("            message: errno.message(),"));
+        lines.push(format!("        }}"));
+        lines.push(format!("    }}"));
+        lines.push(format!("}}\n"));
+
         let content = lines.join("\n");
         std::fs::write(format!("{}/error_map.rs", outdir), content)
             .map_err(|e| format!("Write error map file error: {}", e))?;
@@ -281,6 +413,27 @@ impl Generator {
         Ok(lines)
     }
 
+    #[rustfmt::skip]
+    fn gen_message_descs(msgs: &Vec<ApiMessage>) -> Result<Vec<String>> {
+        let mut lines: Vec<String> = Vec::new();
+
+        lines.push(format!("pub const MESSAGES: &[rsvpp::message::MessageDesc] = &["));
+        for msg in msgs {
+            lines.push(format!("    rsvpp::message::MessageDesc {{ name: \"{}\", crc: \"{}\" }},", msg.name, msg.extra.crc));
+        }
+        lines.push(format!("];\n"));
+
+        // Decode all messages in wire trace
+        lines.push(format!("pub fn register_trace(trace: rsvpp::WireTrace) -> rsvpp::WireTrace {{"));
+        lines.push(format!("    trace"));
+        for msg in msgs {
+            lines.push(format!("        .decode::<{}>()", gen_struct_name(&msg.name)));
+        }
+        lines.push(format!("}}\n"));
+
+        Ok(lines)
+    }
+
     fn gen_union(uni: &ApiUnion, generated_type_set: &mut HashSet<String>) -> Result<Vec<String>> {
         ensure_not_duplicate!(gen_struct_name(&uni.name), generated_type_set);
 
@@ -411,8 +564,8 @@ impl Generator {
         // Gen msg name
         #[rustfmt::skip]
         lines.push(format!("impl rsvpp::message::MessageName for {} {{", struct_name));
-        lines.push(format!("    fn message_name() -> String {{"));
-        lines.push(format!("        \"{}\".to_string()", name));
+        lines.push(format!("    fn message_name() -> &'static str {{"));
+        lines.push(format!("        \"{}\"", name));
         lines.push(format!("    }}"));
         lines.push(format!("}}\n"));
 
@@ -543,7 +696,37 @@ impl Generator {
     }
 
     #[rustfmt::skip]
-    fn gen_services(name: &String, services: &Vec<ApiService>, has_retval_type_set: &HashSet<String>) -> Result<Vec<String>> {
+    fn gen_requests(services: &Vec<ApiService>, request_set: &HashSet<String>, has_retval_type_set: &HashSet<String>, idempotent: &HashSet<String>) -> Result<Vec<String>> {
+        let mut lines: Vec<String> = Vec::new();
+
+        for service in services {
+            if !request_set.contains(&service.req) {
+                continue;
+            }
+
+            let req_type = gen_struct_name(&service.req);
+            let rep_type = gen_struct_name(&service.rep);
+
+            lines.push(format!("impl rsvpp::message::Request for {} {{", req_type));
+            lines.push(format!("    type Reply = {};", rep_type));
+            lines.push(format!("    const IS_STREAM: bool = {};", service.is_stream));
+            if idempotent.contains(&service.req) || is_read_only(&service.req) {
+                lines.push(format!("    const IDEMPOTENT: bool = true;"));
+            }
+            if has_retval_type_set.contains(&rep_type) {
+                lines.push(format!(""));
+                lines.push(format!("    fn check_reply(reply: &Self::Reply) -> rsvpp::Result<()> {{"));
+                lines.push(format!("        check_error(reply.retval() as i32, \"{}\")", service.req));
+                lines.push(format!("    }}"));
+            }
+            lines.push(format!("}}\n"));
+        }
+
+        Ok(lines)
+    }
+
+    #[rustfmt::skip]
+    fn gen_services(name: &String, services: &Vec<ApiService>, has_retval_type_set: &HashSet<String>, request_set: &HashSet<String>) -> Result<Vec<String>> {
         // Skip memclnt
         if name == "memclnt" {
             return Ok(Vec::new());
@@ -553,15 +736,15 @@ impl Generator {
         let mut lines: Vec<String> = Vec::new();
 
         lines.push(format!("pub struct {} {{", struct_name));
-        lines.push(format!("    client: std::sync::Arc<rsvpp::Client>,"));
+        lines.push(format!("    client: rsvpp::ServiceClient,"));
         lines.push(format!("}}\n"));
 
         lines.push(format!("impl {} {{", struct_name));
-        lines.push(format!("    pub fn new(client: std::sync::Arc<rsvpp::Client>) -> Self {{"));
-        lines.push(format!("        Self {{ client }}"));
+        lines.push(format!("    pub fn new<C: Into<rsvpp::ServiceClient>>(client: C) -> Self {{"));
+        lines.push(format!("        Self {{ client: client.into() }}"));
         lines.push(format!("    }}\n"));
         for service in services {
-            lines.extend(Self::gen_service(service, has_retval_type_set)?);
+            lines.extend(Self::gen_service(service, has_retval_type_set, request_set)?);
         }
         lines.push(format!("}}\n"));
 
@@ -569,52 +752,171 @@ impl Generator {
     }
 
     #[rustfmt::skip]
-    fn gen_service(service: &ApiService, has_retval_type_set: &HashSet<String>) -> Result<Vec<String>> {
+    fn gen_service(service: &ApiService, has_retval_type_set: &HashSet<String>, request_set: &HashSet<String>) -> Result<Vec<String>> {
         let mut lines: Vec<String> = Vec::new();
         let func_name = &service.req;
         let req_type = gen_struct_name(&service.req);
         let rep_type = gen_struct_name(&service.rep);
 
         if service.is_stream {
+            lines.push(format!("    pub async fn {}_stream(&self, req: {}) -> rsvpp::Result<impl rsvpp::futures::Stream<Item = rsvpp::Result<{}>> + '_> {{", func_name, req_type, rep_type));
+            lines.push(format!("        let client = self.client.get().await?;"));
+            lines.push(format!("        let stream = client.dump_shared::<{}, {}>(req, rsvpp::CallOptions::default()).await?;", req_type, rep_type));
+            if has_retval_type_set.contains(&rep_type) {
+                lines.push(format!("        Ok(rsvpp::futures::StreamExt::map(stream, |rep| {{"));
+                lines.push(format!("            let rep = rep?;"));
+                lines.push(format!("            check_error(rep.retval() as i32, \"{}\")?;", service.req));
+                lines.push(format!("            Ok(rep)"));
+                lines.push(format!("        }}))"));
+            } else {
+                lines.push(format!("        Ok(stream)"));
+            }
+            lines.push(format!("    }}\n"));
+
             lines.push(format!("    pub async fn {}(&self, req: {}) -> rsvpp::Result<Vec<{}>> {{", func_name, req_type, rep_type));
-            lines.push(format!("        let ctx = self.client.send_msg(req).await?;"));
-            lines.push(format!("        self.client.send_msg_with_ctx(super::vpe::ControlPing::new(), ctx).await?;"));
-
-            lines.push(format!("        let mut arr: Vec<{}> = Vec::new();", rep_type));
-            lines.push(format!("        'outer: loop {{"));
-            lines.push(format!("            for entry in self.client.recv(ctx).await? {{"));
-            lines.push(format!("                if entry.header._vl_msg_id == self.client.get_msg_id::<super::vpe::ControlPingReply>()? {{"));
-            lines.push(format!("                    let rep = super::vpe::ControlPingReply::unpack(&entry.data, 0)?.0;"));
-            lines.push(format!("                    check_error(rep.retval() as i32)?;"));
-            lines.push(format!("                    break 'outer;"));
-            lines.push(format!("                }}"));
-
-            lines.push(format!("                if entry.header._vl_msg_id != self.client.get_msg_id::<{}>()? {{", rep_type));
-            lines.push(format!("                    return Err(rsvpp::Error::msg_id_mismatch(\"Message id mismatch in {}\"));", func_name));
-            lines.push(format!("                }}"));
-
-            lines.push(format!("                let rep = {}::unpack(&entry.data, 0)?.0;", rep_type));
+            lines.push(format!("        let client = self.client.get().await?;"));
+            lines.push(format!("        let reps: Vec<{}> = client.dump_collect(req, rsvpp::CallOptions::default()).await?;", rep_type));
             if has_retval_type_set.contains(&rep_type) {
-                lines.push(format!("                check_error(rep.retval() as i32)?;"));
+                lines.push(format!("        for rep in &reps {{"));
+                lines.push(format!("            check_error(rep.retval() as i32, \"{}\")?;", service.req));
+                lines.push(format!("        }}"));
             }
-            lines.push(format!("                arr.push(rep)"));
-            lines.push(format!("            }}"));
-            lines.push(format!("        }}"));
-            lines.push(format!("        Ok(arr)"));
+            lines.push(format!("        Ok(reps)"));
             lines.push(format!("    }}\n"));
         } else {
             lines.push(format!("    pub async fn {}(&self, req: {}) -> rsvpp::Result<{}> {{", func_name, req_type, rep_type));
-            lines.push(format!("        let ctx = self.client.send_msg(req).await?;"));
-            lines.push(format!("        let rep: {} = self.client.recv_msg(ctx).await?;", rep_type));
+            lines.push(format!("        let client = self.client.get().await?;"));
+            lines.extend(Self::gen_service_call(service, has_retval_type_set, request_set, false));
+            lines.push(format!("        Ok(rep)"));
+            lines.push(format!("    }}\n"));
+        }
+
+        // Gen event subscribers, events are sent to the client which enabled them
+        for event in &service.events {
+            let event_type = gen_struct_name(event);
+            lines.push(format!("    pub async fn subscribe_{}(&self, req: {}) -> rsvpp::Result<rsvpp::Subscription<{}>> {{", event, req_type, event_type));
+            lines.push(format!("        let client = self.client.get().await?;"));
+            if request_set.contains(&service.req) {
+                // Enable request is sent again after reconnect
+                lines.push(format!("        let (sub, _) = client.subscribe_with::<{}, _>(req).await?;", event_type));
+            } else {
+                lines.push(format!("        let sub = client.subscribe::<{}>().await?;", event_type));
+                lines.extend(Self::gen_service_call(service, has_retval_type_set, request_set, false));
+            }
+            lines.push(format!("        Ok(sub)"));
+            lines.push(format!("    }}\n"));
+        }
+
+        Ok(lines)
+    }
+
+    #[rustfmt::skip]
+    fn gen_blocking_services(name: &String, services: &Vec<ApiService>, has_retval_type_set: &HashSet<String>, request_set: &HashSet<String>) -> Result<Vec<String>> {
+        // Skip memclnt
+        if name == "memclnt" {
+            return Ok(Vec::new());
+        }
+
+        let struct_name = format!("{}Service", name).hump();
+        let mut lines: Vec<String> = Vec::new();
+
+        lines.push(format!("pub struct {} {{", struct_name));
+        lines.push(format!("    client: std::sync::Arc<rsvpp::blocking::Client>,"));
+        lines.push(format!("}}\n"));
+
+        lines.push(format!("impl {} {{", struct_name));
+        lines.push(format!("    pub fn new(client: std::sync::Arc<rsvpp::blocking::Client>) -> Self {{"));
+        lines.push(format!("        Self {{ client }}"));
+        lines.push(format!("    }}\n"));
+        for service in services {
+            lines.extend(Self::gen_blocking_service(service, has_retval_type_set, request_set)?);
+        }
+        lines.push(format!("}}\n"));
+
+        Ok(lines)
+    }
+
+    #[rustfmt::skip]
+    fn gen_blocking_service(service: &ApiService, has_retval_type_set: &HashSet<String>, request_set: &HashSet<String>) -> Result<Vec<String>> {
+        let mut lines: Vec<String> = Vec::new();
+        let func_name = &service.req;
+        let req_type = gen_struct_name(&service.req);
+        let rep_type = gen_struct_name(&service.rep);
+
+        if service.is_stream {
+            lines.push(format!("    pub fn {}(&self, req: {}) -> rsvpp::Result<Vec<{}>> {{", func_name, req_type, rep_type));
+            lines.push(format!("        let client = &self.client;"));
+            lines.push(format!("        let reps: Vec<{}> = client.dump(req)?;", rep_type));
             if has_retval_type_set.contains(&rep_type) {
-                lines.push(format!("        check_error(rep.retval() as i32)?;"));
+                lines.push(format!("        for rep in &reps {{"));
+                lines.push(format!("            check_error(rep.retval() as i32, \"{}\")?;", service.req));
+                lines.push(format!("        }}"));
             }
+            lines.push(format!("        Ok(reps)"));
+            lines.push(format!("    }}\n"));
+        } else {
+            lines.push(format!("    pub fn {}(&self, req: {}) -> rsvpp::Result<{}> {{", func_name, req_type, rep_type));
+            lines.push(format!("        let client = &self.client;"));
+            lines.extend(Self::gen_service_call(service, has_retval_type_set, request_set, true));
             lines.push(format!("        Ok(rep)"));
             lines.push(format!("    }}\n"));
         }
 
+        // Gen event subscribers
+        for event in &service.events {
+            let event_type = gen_struct_name(event);
+            lines.push(format!("    pub fn subscribe_{}(&self, req: {}) -> rsvpp::Result<rsvpp::blocking::Subscription<'_, {}>> {{", event, req_type, event_type));
+            lines.push(format!("        let client = &self.client;"));
+            if request_set.contains(&service.req) {
+                lines.push(format!("        let (sub, _) = client.subscribe_with::<{}, _>(req)?;", event_type));
+            } else {
+                lines.push(format!("        let sub = client.subscribe::<{}>()?;", event_type));
+                lines.extend(Self::gen_service_call(service, has_retval_type_set, request_set, true));
+            }
+            lines.push(format!("        Ok(sub)"));
+            lines.push(format!("    }}\n"));
+        }
+
         Ok(lines)
     }
+
+    #[rustfmt::skip]
+    fn gen_service_call(service: &ApiService, has_retval_type_set: &HashSet<String>, request_set: &HashSet<String>, blocking: bool) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        let rep_type = gen_struct_name(&service.rep);
+        let wait = if blocking { "" } else { ".await" };
+
+        if request_set.contains(&service.req) {
+            lines.push(format!("        let rep = client.call(req){}?;", wait));
+        } else {
+            lines.push(format!("        let ctx = client.send_msg(req){}?;", wait));
+            lines.push(format!("        let rep: {} = client.recv_msg(ctx){}?;", rep_type, wait));
+            if has_retval_type_set.contains(&rep_type) {
+                lines.push(format!("        check_error(rep.retval() as i32, \"{}\")?;", service.req));
+            }
+        }
+
+        lines
+    }
+}
+
+/// Requests only reading state, like `show_version`, `sw_interface_dump` or `ip_table_get`
+fn is_read_only(req: &str) -> bool {
+    req.starts_with("show_")
+        || req.starts_with("get_")
+        || req.ends_with("_dump")
+        || req.ends_with("_get")
+        || req.contains("_get_")
+}
+
+/// `NO_SUCH_ENTRY` to `NoSuchEntry`
+fn gen_errno_variant(name: &str) -> String {
+    let variant = name.to_lowercase().hump();
+    if variant.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("E{}", variant)
+    } else {
+        variant
+    }
 }
 
 fn gen_filed_type(ty: &str) -> String {
@@ -5,11 +5,17 @@ struct Config {
     input: String,
     output: String,
     err_header_file: String,
+    blocking: bool,
+    idempotent: Vec<String>,
 }
 
 fn main() -> Result<()> {
     let cfg = parse_cmd()?;
     let mut gen = Generator::new(cfg.output, cfg.input, cfg.err_header_file)?;
+    gen.set_blocking(cfg.blocking);
+    for name in cfg.idempotent {
+        gen.add_idempotent(name);
+    }
     gen.gen()?;
 
     Ok(())
@@ -23,6 +29,17 @@ fn parse_cmd() -> Result<Config> {
     opts.optopt("i", "", "Binapi input dir, like $VPP/share/vpp/api", "DIR");
     opts.optopt("e", "", "Vpp api_errno.h file path", "PATH");
     opts.optopt("o", "", "Rust output dir", "DIR");
+    opts.optflag(
+        "b",
+        "blocking",
+        "Generate blocking services, needs rsvpp feature 'blocking'",
+    );
+    opts.optmulti(
+        "",
+        "idempotent",
+        "Request retried by the client retry policy, besides show/get/dump requests",
+        "NAME",
+    );
     opts.optflag("h", "help", "Print help message");
     let matches = opts.parse(&args[1..])?;
 
@@ -43,5 +60,7 @@ fn parse_cmd() -> Result<Config> {
         input,
         output,
         err_header_file,
+        blocking: matches.opt_present("b"),
+        idempotent: matches.opt_strs("idempotent"),
     })
 }
@@ -71,6 +71,7 @@ pub struct ApiService {
     pub req: String,
     pub rep: String,
     pub is_stream: bool,
+    pub events: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -353,11 +354,18 @@ impl JsonApi {
                 } else {
                     false
                 };
+                let mut events: Vec<String> = Vec::new();
+                if let Some(arr) = v.get("events") {
+                    for e in arr.as_array().ok_or("events must be array")? {
+                        events.push(e.as_str().ok_or("event must be string")?.to_string());
+                    }
+                }
 
                 self.services.push(ApiService {
                     req,
                     rep,
                     is_stream,
+                    events,
                 });
             }
         } else {
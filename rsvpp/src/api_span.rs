@@ -0,0 +1,119 @@
+use std::future::Future;
+
+use crate::{Error, Result};
+
+/// Api operation wrapped in a span with feature `tracing`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Call,
+    Dump,
+    SendMsg,
+    RecvMsg,
+}
+
+/// Span of one api operation, no-op without feature `tracing`
+///
+/// Fields `ctx`, `client_index`, `replies` and `retval` are recorded on the current span by
+/// the `record_*` functions, so they land in the innermost api span.
+pub(crate) struct ApiSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! api_span {
+    ($name:expr, $message:expr) => {
+        tracing::info_span!(
+            $name,
+            message = %$message,
+            ctx = tracing::field::Empty,
+            client_index = tracing::field::Empty,
+            replies = tracing::field::Empty,
+            retval = tracing::field::Empty,
+        )
+    };
+}
+
+impl ApiSpan {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub fn new(op: Op, message: &str) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: match op {
+                Op::Call => api_span!("rsvpp.call", message),
+                Op::Dump => api_span!("rsvpp.dump", message),
+                Op::SendMsg => api_span!("rsvpp.send_msg", message),
+                Op::RecvMsg => api_span!("rsvpp.recv_msg", message),
+            },
+        }
+    }
+
+    /// Run future inside span, failure is recorded as event of span
+    pub async fn run<T, F>(&self, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        #[cfg(feature = "tracing")]
+        let res = tracing::Instrument::instrument(fut, self.span.clone()).await;
+        #[cfg(not(feature = "tracing"))]
+        let res = fut.await;
+
+        if let Err(e) = &res {
+            self.record_error(e);
+        }
+
+        res
+    }
+
+    /// Enter span while polling a stream
+    pub fn enter(&self) -> SpanGuard<'_> {
+        SpanGuard {
+            #[cfg(feature = "tracing")]
+            _entered: self.span.enter(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub fn record_error(&self, e: &Error) {
+        #[cfg(feature = "tracing")]
+        {
+            let _entered = self.span.enter();
+            match e {
+                Error::Timeout(_) => tracing::warn!(error = %e, "timeout"),
+                Error::CrcMismatch(_) => tracing::warn!(error = %e, "crc mismatch"),
+                _ => tracing::debug!(error = %e, "failed"),
+            }
+        }
+    }
+}
+
+pub(crate) struct SpanGuard<'a> {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::Entered<'a>,
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_ctx(ctx: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("ctx", ctx);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_client_index(client_index: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("client_index", client_index);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_replies(count: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("replies", count);
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_retval(retval: i32) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("retval", retval);
+}
@@ -0,0 +1,202 @@
+use futures::StreamExt;
+use pack::Pack;
+
+use crate::{
+    message::{MessageClientId, MessageContext, MessageCrc, MessageId, MessageName, Request},
+    runtime::Runtime,
+    CallOptions, ClientBuilder, Error, MessageEntry, Metrics, ModuleVersion, RecvEntry, Result,
+};
+
+/// Synchronous client, requests are run on a private runtime
+///
+/// With tokio the runtime keeps one worker thread receiving replies and answering keepalive
+/// between calls, async-std and smol use their global executor.
+/// Methods block the current thread, so they must not be called inside an async runtime.
+pub struct Client {
+    inner: crate::Client,
+    rt: Runtime,
+}
+
+impl Client {
+    pub fn connect_unix(sock: &str) -> Result<Self> {
+        Self::from_builder(ClientBuilder::new().socket(sock))
+    }
+
+    pub fn connect_tcp(addr: &str) -> Result<Self> {
+        Self::from_builder(ClientBuilder::new().tcp(addr))
+    }
+
+    pub fn from_builder(builder: ClientBuilder) -> Result<Self> {
+        // Create runtime
+        let rt =
+            Runtime::new().map_err(|e| Error::internal(format!("Create runtime error: {}", e)))?;
+
+        // Create client
+        let inner = rt.block_on(builder.connect())?;
+
+        Ok(Self { inner, rt })
+    }
+
+    /// The async client, use it in futures given to `block_on`
+    pub fn inner(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Run future on the private runtime of client
+    pub fn block_on<F: std::future::Future>(&self, fut: F) -> F::Output {
+        self.rt.block_on(fut)
+    }
+
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.inner.is_healthy()
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.rt.block_on(self.inner.metrics())
+    }
+
+    pub fn set_timeout(&self, ms: u64) {
+        self.inner.set_timeout(ms)
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.inner.timeout()
+    }
+
+    pub fn send_msg<T>(&self, msg: T) -> Result<u32>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+    {
+        self.block_on(self.inner.send_msg(msg))
+    }
+
+    pub fn send_msg_with_ctx<T>(&self, msg: T, ctx: u32) -> Result<u32>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+    {
+        self.block_on(self.inner.send_msg_with_ctx(msg, ctx))
+    }
+
+    pub fn recv_msg<T>(&self, ctx: u32) -> Result<T>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageCrc,
+    {
+        self.block_on(self.inner.recv_msg(ctx))
+    }
+
+    pub fn recv(&self, ctx: u32) -> Result<Vec<RecvEntry>> {
+        self.block_on(self.inner.recv(ctx))
+    }
+
+    pub fn call<Req: Request>(&self, req: Req) -> Result<Req::Reply> {
+        self.block_on(self.inner.call(req))
+    }
+
+    pub fn call_with<Req: Request>(&self, req: Req, opts: CallOptions) -> Result<Req::Reply> {
+        self.block_on(self.inner.call_with(req, opts))
+    }
+
+    /// Send dump request and collect all replies
+    pub fn dump<Req, Rep>(&self, req: Req) -> Result<Vec<Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        self.dump_with(req, CallOptions::default())
+    }
+
+    pub fn dump_with<Req, Rep>(&self, req: Req, opts: CallOptions) -> Result<Vec<Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        self.block_on(self.inner.dump_collect(req, opts))
+    }
+
+    pub fn subscribe<T>(&self) -> Result<Subscription<'_, T>>
+    where
+        T: Pack + MessageName + MessageCrc,
+    {
+        let inner = self.block_on(self.inner.subscribe::<T>())?;
+
+        Ok(Subscription {
+            client: self,
+            inner,
+        })
+    }
+
+    /// See `crate::Client::subscribe_with`
+    pub fn subscribe_with<T, Req>(&self, enable: Req) -> Result<(Subscription<'_, T>, Req::Reply)>
+    where
+        T: Pack + MessageName + MessageCrc,
+        Req: Request,
+    {
+        let (inner, rep) = self.block_on(self.inner.subscribe_with::<T, Req>(enable))?;
+
+        Ok((
+            Subscription {
+                client: self,
+                inner,
+            },
+            rep,
+        ))
+    }
+
+    pub fn close(&self) -> Result<()> {
+        self.block_on(self.inner.close())
+    }
+
+    pub fn message_table(&self) -> Vec<MessageEntry> {
+        self.inner.message_table()
+    }
+
+    pub fn supports<T>(&self) -> bool
+    where
+        T: MessageName + MessageCrc,
+    {
+        self.inner.supports::<T>()
+    }
+
+    pub fn api_versions(&self) -> Result<Vec<ModuleVersion>> {
+        self.block_on(self.inner.api_versions())
+    }
+
+    pub fn has_plugin(&self, name: &str) -> Result<bool> {
+        self.block_on(self.inner.has_plugin(name))
+    }
+
+    pub fn get_msg_id<T>(&self) -> Result<u16>
+    where
+        T: MessageName + MessageCrc,
+    {
+        self.inner.get_msg_id::<T>()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // Unregister before the runtime is gone, no-op if closed already
+        if let Err(e) = self.block_on(self.inner.close()) {
+            log::debug!("Close blocking client error: {}", e);
+        }
+    }
+}
+
+/// Iterator of messages pushed by vpp, see `crate::Subscription`
+pub struct Subscription<'a, T> {
+    client: &'a Client,
+    inner: crate::Subscription<T>,
+}
+
+impl<'a, T: Pack> Iterator for Subscription<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = &mut self.inner;
+        self.client.block_on(inner.next())
+    }
+}
@@ -0,0 +1,179 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    session::CacheConfig,
+    transport::{Endpoint, DEFAULT_CONNECT_TIMEOUT_MS, DEFAULT_TCP_KEEPALIVE_MS},
+    Client, Error, HealthCheck, ReconnectPolicy, Result, RetryPolicy, Transport, WireTrace,
+    CLIENT_NAME,
+};
+use std::sync::Arc;
+
+const SOCKET_ENV: &str = "VPP_API_SOCKET";
+const DEFAULT_SOCKETS: &[&str] = &["/run/vpp/api.sock", "/var/run/vpp/api.sock"];
+
+const DEFAULT_TIMEOUT_MS: u64 = 3 * 1000;
+const MAX_NAME_LENGTH: usize = 63; // `string name[64]` of sockclnt_create
+
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    pub(crate) name: String,
+    pub(crate) socket: Option<String>,
+    pub(crate) tcp: Option<String>,
+    pub(crate) tcp_keepalive: Option<u64>,
+    pub(crate) timeout: u64,
+    pub(crate) connect_timeout: u64,
+    pub(crate) cache_config: CacheConfig,
+    pub(crate) reconnect_policy: ReconnectPolicy,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) reserved_contexts: Vec<RangeInclusive<u32>>,
+    pub(crate) health_check: Option<HealthCheck>,
+    pub(crate) wire_trace: Option<Arc<WireTrace>>,
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Self {
+            name: CLIENT_NAME.to_string(),
+            socket: None,
+            tcp: None,
+            tcp_keepalive: Some(DEFAULT_TCP_KEEPALIVE_MS),
+            timeout: DEFAULT_TIMEOUT_MS,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT_MS,
+            cache_config: CacheConfig::default(),
+            reconnect_policy: ReconnectPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            reserved_contexts: Vec::new(),
+            health_check: None,
+            wire_trace: None,
+        }
+    }
+
+    /// Client name shown in `show api clients`
+    pub fn name<S: ToString>(mut self, name: S) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Socket path, discovered by `ClientBuilder::discover_socket` if not set
+    pub fn socket<S: ToString>(mut self, socket: S) -> Self {
+        self.socket = Some(socket.to_string());
+        self.tcp = None;
+        self
+    }
+
+    /// Connect to api socket forwarded over tcp, `host:port`, instead of unix socket
+    pub fn tcp<S: ToString>(mut self, addr: S) -> Self {
+        self.tcp = Some(addr.to_string());
+        self.socket = None;
+        self
+    }
+
+    /// Idle time in milliseconds before tcp keepalive probes, None to disable
+    pub fn tcp_keepalive(mut self, ms: Option<u64>) -> Self {
+        self.tcp_keepalive = ms;
+        self
+    }
+
+    /// Default timeout of each send and recv in milliseconds
+    pub fn timeout(mut self, ms: u64) -> Self {
+        self.timeout = ms;
+        self
+    }
+
+    /// Timeout of connecting and registering to vpp in milliseconds, also used by each reconnect attempt
+    pub fn connect_timeout(mut self, ms: u64) -> Self {
+        self.connect_timeout = ms;
+        self
+    }
+
+    pub fn cache_config(mut self, config: CacheConfig) -> Self {
+        self.cache_config = config;
+        self
+    }
+
+    pub fn reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// Retry idempotent requests and dumps failed by transient errors, disabled by default
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Contexts never allocated by client, for requests sent by `Client::send_msg_with_ctx`
+    pub fn reserve_contexts(mut self, range: RangeInclusive<u32>) -> Self {
+        self.reserved_contexts.push(range);
+        self
+    }
+
+    /// Ping vpp in background, see `Client::is_healthy`
+    pub fn health_check(mut self, config: HealthCheck) -> Self {
+        self.health_check = Some(config);
+        self
+    }
+
+    /// Log decoded traffic to target `rsvpp::wire`, see `WireTrace`
+    pub fn wire_trace(mut self, trace: WireTrace) -> Self {
+        self.wire_trace = Some(Arc::new(trace));
+        self
+    }
+
+    pub async fn connect(self) -> Result<Client> {
+        self.validate()?;
+
+        let endpoint = match (&self.tcp, &self.socket) {
+            (Some(addr), _) => Endpoint::Tcp {
+                addr: addr.clone(),
+                keepalive: self.tcp_keepalive,
+                timeout: self.connect_timeout,
+            },
+            (None, Some(socket)) => Endpoint::Unix(socket.clone()),
+            (None, None) => Endpoint::Unix(Self::discover_socket()?),
+        };
+
+        Client::connect_with_builder(self, endpoint).await
+    }
+
+    /// Connect over given transport instead of socket, the client can't reconnect after it lost
+    pub async fn connect_transport(self, transport: Arc<dyn Transport>) -> Result<Client> {
+        self.validate()?;
+
+        Client::connect_with_builder(self, Endpoint::custom(transport)).await
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() || self.name.len() > MAX_NAME_LENGTH {
+            return Err(Error::argument(format!(
+                "Client name length must be in 1..={}",
+                MAX_NAME_LENGTH
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Socket path from env `VPP_API_SOCKET`, otherwise the first existing one of default paths
+    pub fn discover_socket() -> Result<String> {
+        if let Ok(socket) = std::env::var(SOCKET_ENV) {
+            log::trace!("Socket from env {}: '{}'", SOCKET_ENV, socket);
+            return Ok(socket);
+        }
+
+        DEFAULT_SOCKETS
+            .iter()
+            .find(|path| std::path::Path::new(path).exists())
+            .map(|path| path.to_string())
+            .ok_or(Error::argument(format!(
+                "Vpp api socket not found in {:?}, set it by env {}",
+                DEFAULT_SOCKETS, SOCKET_ENV
+            )))
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
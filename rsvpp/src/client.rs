@@ -1,61 +1,327 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    any::TypeId,
+    collections::{HashMap, VecDeque},
+    future::Future,
+    marker::PhantomData,
+    os::unix::io::RawFd,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant},
+};
 
+use futures::TryStreamExt;
 use pack::Pack;
-use tokio::sync::Mutex;
 
 use crate::{
+    api_span::{self, ApiSpan, Op},
+    context::ContextAllocator,
+    dump::ClientRef,
     hard_coded_message::{
-        VlApiSockclntCreateReplyT, VlApiSockclntCreateT, VL_API_SOCK_CLNT_CREATE_MSG_ID,
-        VL_API_SOCK_CLNT_CREATE_REP_MSG_ID,
+        VlApiApiVersionsT, VlApiControlPingReplyT, VlApiControlPingT, VlApiSockclntCreateReplyT,
+        VlApiSockclntDeleteReplyT, VlApiSockclntDeleteT,
+    },
+    health::HealthTask,
+    message::{
+        Message, MessageClientId, MessageContext, MessageCrc, MessageDesc, MessageId, MessageName,
+        Request,
     },
-    message::{Message, MessageClientId, MessageContext, MessageCrc, MessageId, MessageName},
-    transport, Error, RecvEntry, Result, Session, CLIENT_NAME,
+    metrics::MetricsRecorder,
+    reconnect::Reconnector,
+    runtime,
+    session::PendingFrame,
+    transport::{self, Endpoint},
+    BatchErrorMode, BatchOptions, CallOptions, ClientBuilder, CompatibilityReport, CrcMismatch,
+    Dump, Error, Metrics, ReconnectPolicy, RecvEntry, Result, RetryPolicy, Session, Subscription,
+    Transport,
 };
 
-const DEFAULT_TIMEOUT_MS: u64 = 3 * 1000;
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MessageEntry {
     id: u16,
+    name: String,
     crc: String,
 }
 
-pub struct Client {
-    sess: Session,
-    ctx: Mutex<u32>,
+impl MessageEntry {
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn crc(&self) -> &str {
+        &self.crc
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleVersion {
+    pub name: String,
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+#[derive(Default)]
+pub(crate) struct Registration {
+    epoch: u64,
     client_index: u32,
     msg_id_map: HashMap<u16, Arc<MessageEntry>>,
     msg_name_map: HashMap<String, Arc<MessageEntry>>,
-    timeout: u64,
+    /// Ids of message types already resolved and crc checked in this connection
+    type_ids: RwLock<HashMap<TypeId, u16>>,
+}
+
+impl Registration {
+    /// Registration of `sockclnt_create_reply` received in transport epoch
+    pub fn new(epoch: u64, msg: &VlApiSockclntCreateReplyT) -> Result<Self> {
+        log::trace!("Init message hash");
+
+        let mut reg = Self {
+            epoch,
+            client_index: msg.index,
+            ..Default::default()
+        };
+        for entry in &msg.message_table {
+            let id = entry.index;
+            let last_underline_index = if let Some(pos) = entry.name.rfind("_") {
+                pos
+            } else {
+                return Err(Error::internal("Missing '_' in table message"));
+            };
+            let name = entry.name[0..last_underline_index].to_string();
+            let crc = entry.name[last_underline_index + 1..].to_string();
+            let msg_entry = Arc::new(MessageEntry {
+                id,
+                name: name.clone(),
+                crc,
+            });
+
+            reg.msg_name_map.insert(name, msg_entry.clone());
+            reg.msg_id_map.insert(id, msg_entry);
+        }
+
+        Ok(reg)
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn client_index(&self) -> u32 {
+        self.client_index
+    }
+
+    pub fn message_names(&self) -> impl Iterator<Item = (u16, String)> + '_ {
+        self.msg_id_map
+            .iter()
+            .map(|(id, entry)| (*id, entry.name.clone()))
+    }
+
+    pub fn lookup(&self, name: &str, crc: &str) -> Result<u16> {
+        let info = self.msg_name_map.get(name).ok_or(Error::argument(format!(
+            "Message '{}' not found in vpp",
+            name
+        )))?;
+
+        // Validate crc
+        if info.crc != crc {
+            return Err(Error::crc_mismatch(format!(
+                "Crc mismatch, generated: {}, cache: {}",
+                crc, info.crc
+            )));
+        }
+
+        Ok(info.id)
+    }
+
+    /// Like `lookup`, the id is resolved once per message type
+    pub fn lookup_type<T>(&self) -> Result<u16>
+    where
+        T: MessageName + MessageCrc,
+    {
+        let key = TypeId::of::<T>();
+        if let Some(id) = self
+            .type_ids
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+        {
+            return Ok(*id);
+        }
+
+        let id = self.lookup(T::message_name(), T::crc())?;
+        self.type_ids
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, id);
+
+        Ok(id)
+    }
+}
+
+pub struct Client {
+    conn: Arc<Reconnector>,
+    sess: Arc<Session>,
+    reg: Arc<RwLock<Registration>>,
+    timeout: Arc<AtomicU64>,
+    healthy: Arc<AtomicBool>,
+    in_flight: AtomicUsize,
+    retry_policy: RwLock<RetryPolicy>,
+    closed: AtomicBool,
+    metrics: MetricsRecorder,
 }
 
 impl Client {
     pub async fn connect_unix(sock: &str) -> Result<Self> {
-        // Create transport
-        log::trace!("Connect unix: '{}'", sock);
-        let trans = transport::unix(sock).await?;
-
-        // Create session
-        let sess = Session::new(trans);
-
-        // Create client
-        let mut client = Self {
-            sess,
-            ctx: Mutex::new(0),
-            client_index: 0,
-            msg_id_map: HashMap::new(),
-            msg_name_map: HashMap::new(),
-            timeout: DEFAULT_TIMEOUT_MS,
-        };
+        ClientBuilder::new().socket(sock).connect().await
+    }
+
+    /// Connect over custom transport, see `ClientBuilder::connect_transport`
+    pub async fn connect_with_transport(transport: Arc<dyn Transport>) -> Result<Self> {
+        ClientBuilder::new().connect_transport(transport).await
+    }
+
+    /// Use connected unix socket, e.g. from socket activation or fd passing
+    pub async fn from_std(stream: std::os::unix::net::UnixStream) -> Result<Self> {
+        Self::connect_with_transport(transport::unix_from_std(stream)?).await
+    }
+
+    /// Like `from_std`, takes ownership of fd
+    ///
+    /// # Safety
+    ///
+    /// fd must be an open unix stream socket not owned by anything else.
+    pub async unsafe fn from_raw_fd(fd: RawFd) -> Result<Self> {
+        Self::connect_with_transport(transport::unix_from_raw_fd(fd)?).await
+    }
+
+    /// Connect to api socket forwarded over tcp, e.g. `socat TCP-LISTEN:5002 UNIX-CONNECT:/run/vpp/api.sock`
+    pub async fn connect_tcp(addr: &str) -> Result<Self> {
+        ClientBuilder::new().tcp(addr).connect().await
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub(crate) async fn connect_with_builder(
+        builder: ClientBuilder,
+        endpoint: Endpoint,
+    ) -> Result<Self> {
+        let connect_timeout = builder.connect_timeout;
+
+        Self::with_timeout(connect_timeout, "Connect", async move {
+            // Create transport
+            log::trace!("Connect '{}'", endpoint);
+            let trans = endpoint.connect().await?;
+
+            // Create session
+            let sess = Arc::new(Session::with_options(
+                trans,
+                builder.cache_config,
+                builder.wire_trace,
+                &builder.name,
+            ));
+
+            // Create client
+            let reg = Arc::new(RwLock::new(Registration::default()));
+            let timeout = Arc::new(AtomicU64::new(builder.timeout));
+            let conn = Arc::new(Reconnector::new(
+                endpoint,
+                &builder.name,
+                sess.clone(),
+                Arc::new(ContextAllocator::new(builder.reserved_contexts)),
+                reg.clone(),
+                timeout.clone(),
+                connect_timeout,
+                builder.reconnect_policy,
+            ));
+            let client = Self {
+                conn,
+                sess,
+                reg,
+                timeout,
+                healthy: Arc::new(AtomicBool::new(true)),
+                in_flight: AtomicUsize::new(0),
+                retry_policy: RwLock::new(builder.retry_policy),
+                closed: AtomicBool::new(false),
+                metrics: MetricsRecorder::new(&builder.name),
+            };
+
+            // Init client
+            client.conn.init().await?;
 
-        // Init client
-        client.init().await?;
+            // Start health check
+            if let Some(config) = builder.health_check {
+                HealthTask::start(Arc::downgrade(&client.conn), client.healthy.clone(), config);
+            }
 
-        Ok(client)
+            Ok(client)
+        })
+        .await
     }
 
-    pub fn set_timeout(&mut self, ms: u64) {
-        self.timeout = ms;
+    /// Client name shown in `show api clients`
+    pub fn name(&self) -> &str {
+        self.conn.name()
+    }
+
+    /// False if transport lost or health check failed, see `ClientBuilder::health_check`
+    pub fn is_healthy(&self) -> bool {
+        !self.closed.load(Ordering::SeqCst)
+            && self.conn.is_connected()
+            && self.healthy.load(Ordering::SeqCst)
+    }
+
+    /// Number of calls and dumps waiting for reply
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn in_flight_counter(&self) -> &AtomicUsize {
+        &self.in_flight
+    }
+
+    pub(crate) fn metrics_recorder(&self) -> &MetricsRecorder {
+        &self.metrics
+    }
+
+    /// Snapshot of request counters and session cache, see `Metrics`
+    pub async fn metrics(&self) -> Metrics {
+        Metrics {
+            messages: self.metrics.messages(),
+            session: self.sess.metrics().await,
+        }
+    }
+
+    /// Default timeout of each send and recv in milliseconds
+    pub fn set_timeout(&self, ms: u64) {
+        self.timeout.store(ms, Ordering::SeqCst);
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout.load(Ordering::SeqCst)
+    }
+
+    pub fn reconnect_policy(&self) -> ReconnectPolicy {
+        self.conn.policy()
+    }
+
+    pub fn set_reconnect_policy(&self, policy: ReconnectPolicy) {
+        self.conn.set_policy(policy);
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
     }
 
     pub async fn send_msg<T>(&self, msg: T) -> Result<u32>
@@ -63,30 +329,580 @@ impl Client {
         T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
     {
         let ctx = self.next_ctx().await;
-        Ok(self.internal_send_msg(msg, ctx).await?)
+        ApiSpan::new(Op::SendMsg, T::message_name())
+            .run(self.internal_send_msg(msg, ctx, None))
+            .await
     }
 
+    /// Send with ctx of a request sent by `send_msg`, e.g. `control_ping` after a dump, or with a
+    /// caller chosen ctx, which must be in `ClientBuilder::reserve_contexts` and not in use
     pub async fn send_msg_with_ctx<T>(&self, msg: T, ctx: u32) -> Result<u32>
     where
         T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
     {
-        Ok(self.internal_send_msg(msg, ctx).await?)
+        let reserved = self.conn.contexts().is_reserved(ctx);
+        match (reserved, self.sess.is_outstanding(ctx).await) {
+            // Handed out by `send_msg` and still waiting for reply
+            (false, true) => {}
+            // Any other ctx may collide with allocated ones
+            (false, false) => {
+                return Err(Error::argument(format!("Context {} is not reserved", ctx)))
+            }
+            (true, true) => return Err(Error::argument(format!("Context {} is in use", ctx))),
+            (true, false) => {}
+        }
+
+        ApiSpan::new(Op::SendMsg, T::message_name())
+            .run(self.internal_send_msg(msg, ctx, None))
+            .await
     }
 
     pub async fn recv_msg<T>(&self, ctx: u32) -> Result<T>
     where
         T: Pack + MessageName + MessageId + MessageContext + MessageCrc,
     {
-        let msg_id = self.get_msg_id::<T>()?;
+        ApiSpan::new(Op::RecvMsg, T::message_name())
+            .run(self.internal_recv_msg(CtxGuard::new(self, ctx), None))
+            .await
+    }
+
+    /// Send request and receive its reply, the reply is checked by `Request::check_reply`
+    pub async fn call<Req: Request>(&self, req: Req) -> Result<Req::Reply> {
+        self.call_with(req, CallOptions::default()).await
+    }
+
+    pub async fn call_with<Req: Request>(&self, req: Req, opts: CallOptions) -> Result<Req::Reply> {
+        if Req::IS_STREAM {
+            return Err(Error::argument(format!(
+                "Stream request '{}' must be sent by dump",
+                Req::message_name()
+            )));
+        }
+
+        let name = Req::message_name();
+        let policy = match opts.retry {
+            Some(policy) => policy,
+            None if Req::IDEMPOTENT => self.retry_policy(),
+            None => RetryPolicy::disabled(),
+        };
+        let deadline = opts.resolve_deadline();
+        let span = ApiSpan::new(Op::Call, name);
+        let mut req = CallRequest::new(req, policy.max_attempts > 1);
+        let mut attempt = 1;
+        loop {
+            let started = Instant::now();
+            self.metrics.request(name);
+
+            let res = span
+                .run(async {
+                    Self::with_deadline(deadline, async {
+                        let ctx = self.next_ctx().await;
+                        let guard = CtxGuard::<Req::Reply>::new(self, ctx);
+                        self.send_request(&mut req, ctx, deadline).await?;
+
+                        self.internal_recv_msg(guard, deadline).await
+                    })
+                    .await
+                    .and_then(|rep| Req::check_reply(&rep).map(|_| rep))
+                })
+                .await;
+            self.metrics
+                .complete(name, started, res.as_ref().map(|_| ()));
+
+            match res {
+                Err(e)
+                    if req.can_resend()
+                        && self.backoff(&policy, attempt, &e, name, deadline).await =>
+                {
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Send requests pipelined, replies are returned in request order
+    ///
+    /// Up to `window` requests are kept in flight and written to transport in batches. Sending
+    /// stops after a failed write, or a failed reply in `BatchErrorMode::StopOnError`, so the
+    /// returned replies may be fewer than requests.
+    pub async fn send_many<Req, I>(&self, reqs: I, opts: BatchOptions) -> Vec<Result<Req::Reply>>
+    where
+        Req: Request,
+        I: IntoIterator<Item = Req>,
+    {
+        let window = opts.window.max(1);
+        let mut reqs = reqs.into_iter();
+        let mut in_flight: VecDeque<(usize, CtxGuard<'_, Req::Reply>)> = VecDeque::new();
+        // One slot per request taken from `reqs`, by position
+        let mut replies: Vec<Option<Result<Req::Reply>>> = Vec::new();
+        let mut stopped = Req::IS_STREAM;
+
+        if Req::IS_STREAM {
+            replies.push(Some(Err(Error::argument(format!(
+                "Stream request '{}' must be sent by dump",
+                Req::message_name()
+            )))));
+        }
+
+        loop {
+            // Refill window in one write when half of it is free
+            if !stopped && in_flight.len() <= window / 2 {
+                stopped = self
+                    .send_batch(
+                        &mut reqs,
+                        &mut in_flight,
+                        &mut replies,
+                        window,
+                        opts.error_mode,
+                    )
+                    .await;
+            }
+
+            // Receive in order
+            let (index, guard) = match in_flight.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            let started = guard.started;
+            let res = self
+                .internal_recv_msg(guard, None)
+                .await
+                .and_then(|rep| Req::check_reply(&rep).map(|_| rep));
+            self.metrics
+                .complete(Req::message_name(), started, res.as_ref().map(|_| ()));
+            if res.is_err() && opts.error_mode == BatchErrorMode::StopOnError {
+                stopped = true;
+            }
+            replies[index] = Some(res);
+        }
+
+        replies
+            .into_iter()
+            .map(|rep| rep.unwrap_or_else(|| Err(Error::internal("Request without reply"))))
+            .collect()
+    }
+
+    /// Send requests until window is full, failed requests get their result in `replies`
+    ///
+    /// Returns whether sending should stop.
+    async fn send_batch<'a, Req, I>(
+        &'a self,
+        reqs: &mut I,
+        in_flight: &mut VecDeque<(usize, CtxGuard<'a, Req::Reply>)>,
+        replies: &mut Vec<Option<Result<Req::Reply>>>,
+        window: usize,
+        error_mode: BatchErrorMode,
+    ) -> bool
+    where
+        Req: Request,
+        I: Iterator<Item = Req>,
+    {
+        if let Err(e) = self.ensure_sendable().await {
+            // Fail the next request, the rest aren't sent
+            if reqs.next().is_some() {
+                replies.push(Some(Err(e)));
+            }
+            return true;
+        }
+
+        // Encode
+        let mut stop = false;
+        let mut frames: Vec<(u32, PendingFrame)> = Vec::new();
+        let mut guards: Vec<(usize, CtxGuard<'a, Req::Reply>)> = Vec::new();
+        while in_flight.len() + frames.len() < window {
+            let req = match reqs.next() {
+                Some(req) => req,
+                None => break,
+            };
+            let index = replies.len();
+            replies.push(None);
+
+            let ctx = self.next_ctx().await;
+            self.metrics.request(Req::message_name());
+            let guard = CtxGuard::new(self, ctx);
+            match self.encode_frame(req, ctx) {
+                Ok(frame) => {
+                    frames.push((ctx, frame));
+                    guards.push((index, guard));
+                }
+                Err(e) => {
+                    // Not sent, nothing to discard
+                    guard.disarm();
+                    self.metrics
+                        .complete(Req::message_name(), Instant::now(), Err(&e));
+                    replies[index] = Some(Err(e));
+                    if error_mode == BatchErrorMode::StopOnError {
+                        stop = true;
+                        break;
+                    }
+                }
+            }
+        }
+        if frames.is_empty() {
+            return stop;
+        }
+
+        // Send, session stops tracking the batch if failed
+        log::trace!("Send batch of {} requests", frames.len());
+        if let Err(e) = self.sess.send_requests(frames, self.timeout()).await {
+            // The first request of batch gets the error itself
+            let msg = e.to_string();
+            let mut err = Some(e);
+            for (index, guard) in guards {
+                guard.disarm();
+                let e = err
+                    .take()
+                    .unwrap_or_else(|| Error::internal(format!("Batch not sent: {}", msg)));
+                replies[index] = Some(Err(e));
+            }
+            return true;
+        }
+        in_flight.extend(guards);
+
+        stop
+    }
+
+    /// Send dump request followed by `control_ping`, replies are received lazily
+    pub async fn dump<Req, Rep>(&self, req: Req) -> Result<Dump<'_, Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        self.dump_with(req, CallOptions::default()).await
+    }
+
+    /// Like `dump`, the deadline of options covers all replies of the dump
+    pub async fn dump_with<Req, Rep>(&self, req: Req, opts: CallOptions) -> Result<Dump<'_, Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        let deadline = opts.resolve_deadline();
+        let started = Instant::now();
+        let span = ApiSpan::new(Op::Dump, Req::message_name());
+        let ctx = span
+            .run(self.send_dump(&mut CallRequest::new(req, false), deadline))
+            .await?;
+
+        Ok(Dump::new(
+            ClientRef::Borrowed(self),
+            ctx,
+            deadline,
+            Req::message_name(),
+            started,
+            span,
+        ))
+    }
+
+    /// Like `dump_with`, the returned stream holds a reference count of client
+    pub async fn dump_shared<Req, Rep>(
+        self: &Arc<Self>,
+        req: Req,
+        opts: CallOptions,
+    ) -> Result<Dump<'static, Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        let deadline = opts.resolve_deadline();
+        let started = Instant::now();
+        let span = ApiSpan::new(Op::Dump, Req::message_name());
+        let ctx = span
+            .run(self.send_dump(&mut CallRequest::new(req, false), deadline))
+            .await?;
+
+        Ok(Dump::new(
+            ClientRef::Shared(self.clone()),
+            ctx,
+            deadline,
+            Req::message_name(),
+            started,
+            span,
+        ))
+    }
+
+    /// Like `dump_with` and collect all replies, the whole dump is retried by the retry policy
+    ///
+    /// Dumps are read only, so the client retry policy applies to every dump.
+    pub async fn dump_collect<Req, Rep>(&self, req: Req, opts: CallOptions) -> Result<Vec<Rep>>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc,
+    {
+        let name = Req::message_name();
+        let policy = opts.retry.unwrap_or_else(|| self.retry_policy());
+        let deadline = opts.resolve_deadline();
+        let mut req = CallRequest::new(req, policy.max_attempts > 1);
+        let mut attempt = 1;
+        loop {
+            let started = Instant::now();
+            let span = ApiSpan::new(Op::Dump, name);
+            let res = match span.run(self.send_dump(&mut req, deadline)).await {
+                Ok(ctx) => {
+                    Dump::new(
+                        ClientRef::Borrowed(self),
+                        ctx,
+                        deadline,
+                        name,
+                        started,
+                        span,
+                    )
+                    .try_collect()
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Err(e)
+                    if req.can_resend()
+                        && self.backoff(&policy, attempt, &e, name, deadline).await =>
+                {
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn send_dump<Req>(
+        &self,
+        req: &mut CallRequest<Req>,
+        deadline: Option<Instant>,
+    ) -> Result<u32>
+    where
+        Req: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+    {
+        let name = Req::message_name();
+        let started = Instant::now();
+        self.metrics.request(name);
+
+        let res = Self::with_deadline(deadline, async {
+            let ctx = self.next_ctx().await;
+            let guard = CtxGuard::<VlApiControlPingReplyT>::new(self, ctx);
+            self.send_request(req, ctx, deadline).await?;
+            self.internal_send_msg(VlApiControlPingT::default(), ctx, deadline)
+                .await?;
+
+            // Dump discards remaining replies on drop
+            guard.disarm();
 
-        Ok(self.sess.recv_single_msg(ctx, msg_id, self.timeout).await?)
+            Ok(ctx)
+        })
+        .await;
+
+        // Replies are recorded by `Dump`
+        if res.is_err() {
+            self.metrics
+                .complete(name, started, res.as_ref().map(|_| ()));
+        }
+
+        res
     }
 
     pub async fn recv(&self, ctx: u32) -> Result<Vec<RecvEntry>> {
-        Ok(self.sess.recv(ctx, self.timeout).await?)
+        self.recv_until(ctx, None).await
+    }
+
+    /// Receive replies of ctx, waiting until deadline or the client timeout if no deadline
+    pub(crate) async fn recv_until(
+        &self,
+        ctx: u32,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<RecvEntry>> {
+        loop {
+            match self.sess.recv(ctx, self.remaining(deadline)).await {
+                Err(Error::Disconnected(e)) => {
+                    if !self.reconnect_policy().enabled {
+                        self.sess.release(ctx).await;
+                        return Err(Error::Disconnected(e));
+                    }
+
+                    if let Err(e) = self.conn.reconnect().await {
+                        self.sess.release(ctx).await;
+                        return Err(e);
+                    }
+
+                    // Request was not resent, reply will never come
+                    if self.sess.is_lost(ctx).await {
+                        self.sess.release(ctx).await;
+                        return Err(Error::disconnected(format!(
+                            "Request of ctx {} lost after reconnect",
+                            ctx
+                        )));
+                    }
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Receive messages of type T pushed by vpp, e.g. events enabled by `want_*` requests
+    pub async fn subscribe<T>(&self) -> Result<Subscription<T>>
+    where
+        T: Pack + MessageName + MessageCrc,
+    {
+        let msg_id = self.get_msg_id::<T>()?;
+        let rx = self
+            .sess
+            .subscribe(msg_id, T::message_name(), T::crc())
+            .await;
+
+        Ok(Subscription::new(rx))
     }
 
-    async fn internal_send_msg<T>(&self, msg: T, ctx: u32) -> Result<u32>
+    /// Subscribe to T and send `enable`, e.g. `want_interface_events`
+    ///
+    /// Vpp forgets enabled events when the connection is lost, `enable` is sent again after each
+    /// reconnect until the subscription dropped.
+    pub async fn subscribe_with<T, Req>(&self, enable: Req) -> Result<(Subscription<T>, Req::Reply)>
+    where
+        T: Pack + MessageName + MessageCrc,
+        Req: Request,
+    {
+        // Subscribe first, events may come before reply
+        let sub = self.subscribe::<T>().await?;
+
+        let name = Req::message_name();
+        let started = Instant::now();
+        self.metrics.request(name);
+        let mut req = CallRequest::new(enable, true);
+        let res = ApiSpan::new(Op::Call, name)
+            .run(async {
+                let ctx = self.next_ctx().await;
+                let guard = CtxGuard::<Req::Reply>::new(self, ctx);
+                self.send_request(&mut req, ctx, None).await?;
+
+                self.internal_recv_msg(guard, None)
+                    .await
+                    .and_then(|rep| Req::check_reply(&rep).map(|_| rep))
+            })
+            .await;
+        self.metrics
+            .complete(name, started, res.as_ref().map(|_| ()));
+        let rep = res?;
+
+        let sub = match req.frame {
+            Some(frame) => sub.with_enable(self.conn.add_enable(frame)),
+            None => sub,
+        };
+
+        Ok((sub, rep))
+    }
+
+    /// Unregister from vpp, pending requests are given one timeout to finish
+    pub async fn close(&self) -> Result<()> {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        log::trace!("Close client");
+
+        // Wait pending requests
+        if let Err(e) = self.sess.drain(self.timeout()).await {
+            log::warn!("Drain pending requests error: {}", e);
+        }
+
+        // Unregister, vpp is gone if transport lost
+        let res = if self.conn.is_connected() {
+            self.sockclnt_delete().await
+        } else {
+            Ok(())
+        };
+
+        self.sess.close().await;
+
+        res
+    }
+
+    async fn sockclnt_delete(&self) -> Result<()> {
+        let ctx = self.next_ctx().await;
+        let msg = self.sockclnt_delete_msg(ctx)?;
+        log::trace!("Send sockclnt delete");
+        self.sess.send_msg(msg, self.timeout()).await?;
+
+        log::trace!("Wait sockclnt delete reply");
+        let rep_id = self.get_msg_id::<VlApiSockclntDeleteReplyT>()?;
+        let rep: VlApiSockclntDeleteReplyT = self
+            .sess
+            .recv_single_msg(ctx, rep_id, self.timeout())
+            .await?;
+        if rep.response != 0 {
+            return Err(Error::vpp_api(
+                rep.response,
+                None,
+                VlApiSockclntDeleteT::message_name(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn sockclnt_delete_msg(&self, ctx: u32) -> Result<Message<VlApiSockclntDeleteT>> {
+        let msg_id = self.get_msg_id::<VlApiSockclntDeleteT>()?;
+        let client_index = self.reg().client_index;
+
+        Ok(Message::new(VlApiSockclntDeleteT {
+            _vl_msg_id: msg_id,
+            client_index,
+            context: ctx,
+            index: client_index,
+        }))
+    }
+
+    async fn internal_send_msg<T>(&self, msg: T, ctx: u32, deadline: Option<Instant>) -> Result<u32>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+    {
+        self.send_request(&mut CallRequest::new(msg, false), ctx, deadline)
+            .await
+    }
+
+    async fn send_request<T>(
+        &self,
+        req: &mut CallRequest<T>,
+        ctx: u32,
+        deadline: Option<Instant>,
+    ) -> Result<u32>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
+    {
+        self.ensure_sendable().await?;
+
+        let frame = match (req.msg.take(), req.frame.as_mut()) {
+            (Some(msg), _) => {
+                let frame = self.encode_frame(msg, ctx)?;
+                if req.keep_frame {
+                    req.frame = Some(frame.clone());
+                }
+                frame
+            }
+            (None, Some(frame)) => {
+                // Resend with new ctx, message id may change after reconnect
+                self.conn.patch_frame(frame)?;
+                self.conn.patch_ctx(frame, ctx)?;
+                frame.clone()
+            }
+            (None, None) => return Err(Error::internal("Request already sent")),
+        };
+        api_span::record_ctx(ctx);
+        api_span::record_client_index(self.reg().client_index);
+        self.sess
+            .send_requests(vec![(ctx, frame)], self.remaining(deadline))
+            .await?;
+
+        Ok(ctx)
+    }
+
+    async fn ensure_sendable(&self) -> Result<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::closed("Client closed"));
+        }
+
+        self.conn.ensure_connected().await
+    }
+
+    fn encode_frame<T>(&self, msg: T, ctx: u32) -> Result<PendingFrame>
     where
         T: Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc,
     {
@@ -94,94 +910,282 @@ impl Client {
         let msg = msg
             .set_message_id(msg_id)
             .set_context(ctx)
-            .set_client_index(self.client_index);
+            .set_client_index(self.reg().client_index);
+
+        Ok(PendingFrame {
+            name: T::message_name(),
+            crc: T::crc(),
+            buf: Message::new(msg).encode()?,
+        })
+    }
 
-        self.sess.send_msg(Message::new(msg), self.timeout).await?;
+    async fn internal_recv_msg<T>(
+        &self,
+        guard: CtxGuard<'_, T>,
+        deadline: Option<Instant>,
+    ) -> Result<T>
+    where
+        T: Pack + MessageName + MessageId + MessageContext + MessageCrc,
+    {
+        let ctx = guard.ctx;
+        api_span::record_ctx(ctx);
+        let entries = self.recv_until(ctx, deadline).await?;
+        self.sess.release(ctx).await;
+        guard.disarm();
 
-        Ok(ctx)
+        api_span::record_replies(entries.len());
+        // Replies start with msg id and context, followed by retval
+        if T::message_name().ends_with("_reply") {
+            let retval = entries
+                .first()
+                .and_then(|entry| entry.data.get(6..))
+                .and_then(|buf| i32::unpack(buf, 0).ok());
+            if let Some((retval, _)) = retval {
+                api_span::record_retval(retval);
+            }
+        }
+
+        // Message id may change after reconnect, resolve it after reply received
+        let msg_id = self.get_msg_id::<T>()?;
+
+        Session::decode_single_msg(entries, msg_id)
     }
 
-    pub fn get_msg_id<T>(&self) -> Result<u16>
+    async fn with_deadline<T, F>(deadline: Option<Instant>, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let deadline = match deadline {
+            Some(deadline) if deadline <= Instant::now() => {
+                return Err(Error::timeout("Call deadline exceeded"))
+            }
+            Some(deadline) => deadline,
+            None => return fut.await,
+        };
+
+        runtime::timeout_at(deadline, fut)
+            .await
+            .unwrap_or_else(|| Err(Error::timeout("Call deadline exceeded")))
+    }
+
+    pub(crate) async fn with_timeout<T, F>(ms: u64, what: &str, fut: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        runtime::timeout(Duration::from_millis(ms), fut)
+            .await
+            .unwrap_or_else(|| Err(Error::timeout(format!("{} timeout", what))))
+    }
+
+    /// Wait before retrying failed attempt `attempt`, false if it shouldn't be retried
+    async fn backoff(
+        &self,
+        policy: &RetryPolicy,
+        attempt: usize,
+        e: &Error,
+        name: &str,
+        deadline: Option<Instant>,
+    ) -> bool {
+        if self.closed.load(Ordering::SeqCst) || !policy.should_retry(attempt, e) {
+            return false;
+        }
+
+        let delay = policy.delay(attempt);
+        if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+            return false;
+        }
+
+        log::debug!(
+            "Retry '{}' in {:?}, attempt {} failed: {}",
+            name,
+            delay,
+            attempt,
+            e
+        );
+        runtime::sleep(delay).await;
+
+        true
+    }
+
+    /// Milliseconds left before deadline, client timeout if no deadline
+    fn remaining(&self, deadline: Option<Instant>) -> u64 {
+        match deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .as_millis() as u64,
+            None => self.timeout(),
+        }
+    }
+
+    /// Message table of vpp, sorted by message id
+    pub fn message_table(&self) -> Vec<MessageEntry> {
+        let mut table: Vec<MessageEntry> = self
+            .reg()
+            .msg_id_map
+            .values()
+            .map(|entry| entry.as_ref().clone())
+            .collect();
+        table.sort_by_key(|entry| entry.id);
+
+        table
+    }
+
+    /// Whether vpp has the message with the same crc
+    pub fn supports<T>(&self) -> bool
     where
         T: MessageName + MessageCrc,
     {
-        let name = T::message_name();
-        let info = self.msg_name_map.get(&name).ok_or(Error::argument(format!(
-            "Message '{}' not found in vpp",
-            name
-        )))?;
+        self.get_msg_id::<T>().is_ok()
+    }
 
-        // Validate crc
-        if info.crc != T::crc() {
-            return Err(Error::crc_mismatch(format!(
-                "Crc mismatch, generated: {}, cache: {}",
-                T::crc(),
-                info.crc
-            )));
+    /// Check generated messages against vpp message table
+    pub fn compatibility_report(&self, messages: &[MessageDesc]) -> CompatibilityReport {
+        let reg = self.reg();
+        let mut report = CompatibilityReport::default();
+
+        for desc in messages {
+            match reg.msg_name_map.get(desc.name) {
+                None => report.missing.push(*desc),
+                Some(entry) if entry.crc != desc.crc => report.crc_mismatch.push(CrcMismatch {
+                    name: desc.name,
+                    expected: desc.crc,
+                    actual: entry.crc.clone(),
+                }),
+                _ => {}
+            }
         }
 
-        Ok(info.id)
+        report
     }
 
-    async fn init(&mut self) -> Result<()> {
-        log::trace!("Init client");
+    /// Versions of api modules loaded by vpp, including plugins
+    pub async fn api_versions(&self) -> Result<Vec<ModuleVersion>> {
+        let rep = self.call(VlApiApiVersionsT::default()).await?;
 
-        let ctx = self.next_ctx().await;
+        Ok(rep
+            .api_versions
+            .into_iter()
+            .map(|v| ModuleVersion {
+                name: v.name,
+                major: v.major,
+                minor: v.minor,
+                patch: v.patch,
+            })
+            .collect())
+    }
 
-        // Send socket client init message
-        let sock_clnt_create_msg = Message::new(VlApiSockclntCreateT {
-            _vl_msg_id: VL_API_SOCK_CLNT_CREATE_MSG_ID,
-            context: ctx,
-            name: CLIENT_NAME.to_string(),
-        });
-        log::trace!("Send sockclnt create");
-        self.sess
-            .send_msg(sock_clnt_create_msg, self.timeout)
-            .await?;
+    pub async fn has_plugin(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .api_versions()
+            .await?
+            .iter()
+            .any(|module| module.name == name))
+    }
 
-        // Get vpp msg table
-        // XXX: sockclnt reply context is 0
-        log::trace!("Wait sockclnt create reply");
-        let sock_clnt_rep_msg: VlApiSockclntCreateReplyT = self
-            .sess
-            .recv_single_msg(0, VL_API_SOCK_CLNT_CREATE_REP_MSG_ID, self.timeout)
-            .await?;
+    pub fn get_msg_id<T>(&self) -> Result<u16>
+    where
+        T: MessageName + MessageCrc,
+    {
+        self.reg().lookup_type::<T>()
+    }
 
-        // Update client index
-        log::trace!("Client index: {}", sock_clnt_rep_msg.index);
-        self.client_index = sock_clnt_rep_msg.index;
+    pub(crate) fn session(&self) -> &Session {
+        &self.sess
+    }
 
-        // Init hash
-        self.init_msg_hash(&sock_clnt_rep_msg)?;
+    fn reg(&self) -> RwLockReadGuard<'_, Registration> {
+        self.reg.read().unwrap_or_else(|e| e.into_inner())
+    }
 
-        Ok(())
+    async fn next_ctx(&self) -> u32 {
+        self.conn.next_ctx().await
     }
+}
 
-    fn init_msg_hash(&mut self, msg: &VlApiSockclntCreateReplyT) -> Result<()> {
-        log::trace!("Init message hash");
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.closed.load(Ordering::SeqCst) || !self.conn.is_connected() {
+            return;
+        }
 
-        for entry in &msg.message_table {
-            let id = entry.index;
-            let last_underline_index = if let Some(pos) = entry.name.rfind("_") {
-                pos
-            } else {
-                return Err(Error::internal("Missing '_' in table message"));
-            };
-            let name = entry.name[0..last_underline_index].to_string();
-            let crc = entry.name[last_underline_index + 1..].to_string();
-            let msg_entry = Arc::new(MessageEntry { id, crc });
+        // Best effort, reply is never read
+        log::trace!("Send sockclnt delete on drop");
+        let res = self
+            .sockclnt_delete_msg(0)
+            .and_then(|msg| self.sess.send_msg_detached(msg));
+        if let Err(e) = res {
+            log::debug!("Send sockclnt delete on drop error: {}", e);
+        }
+    }
+}
+
+/// Request of a call, the encoded frame is kept to send it again with a new ctx on retry
+struct CallRequest<T> {
+    msg: Option<T>,
+    frame: Option<PendingFrame>,
+    keep_frame: bool,
+}
 
-            self.msg_name_map.insert(name, msg_entry.clone());
-            self.msg_id_map.insert(id, msg_entry);
+impl<T> CallRequest<T> {
+    fn new(msg: T, keep_frame: bool) -> Self {
+        Self {
+            msg: Some(msg),
+            frame: None,
+            keep_frame,
         }
+    }
 
-        Ok(())
+    fn can_resend(&self) -> bool {
+        self.msg.is_some() || self.frame.is_some()
     }
+}
 
-    async fn next_ctx(&self) -> u32 {
-        let mut ctx = self.ctx.lock().await;
-        *ctx += 1;
+/// Discard replies of ctx if the request is abandoned before its reply received
+struct CtxGuard<'a, T>
+where
+    T: MessageName + MessageCrc,
+{
+    client: &'a Client,
+    ctx: u32,
+    armed: bool,
+    started: Instant,
+    _marker: PhantomData<fn() -> T>,
+}
 
-        *ctx
+impl<'a, T> CtxGuard<'a, T>
+where
+    T: MessageName + MessageCrc,
+{
+    fn new(client: &'a Client, ctx: u32) -> Self {
+        client.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            client,
+            ctx,
+            armed: true,
+            started: Instant::now(),
+            _marker: PhantomData,
+        }
+    }
+
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl<'a, T> Drop for CtxGuard<'a, T>
+where
+    T: MessageName + MessageCrc,
+{
+    fn drop(&mut self) {
+        self.client.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if !self.armed {
+            return;
+        }
+
+        match self.client.get_msg_id::<T>() {
+            Ok(msg_id) => self.client.sess.discard(self.ctx, msg_id),
+            Err(e) => log::warn!("Discard ctx {} error: {}", self.ctx, e),
+        }
     }
 }
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use crate::message::MessageDesc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrcMismatch {
+    pub name: &'static str,
+    /// Crc of generated message
+    pub expected: &'static str,
+    /// Crc reported by vpp
+    pub actual: String,
+}
+
+/// Generated messages which can't be used against the connected vpp
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Messages not found in vpp message table
+    pub missing: Vec<MessageDesc>,
+    pub crc_mismatch: Vec<CrcMismatch>,
+}
+
+impl CompatibilityReport {
+    pub fn is_compatible(&self) -> bool {
+        self.missing.is_empty() && self.crc_mismatch.is_empty()
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_compatible() {
+            return write!(f, "All messages are compatible");
+        }
+
+        write!(
+            f,
+            "{} missing, {} crc mismatch",
+            self.missing.len(),
+            self.crc_mismatch.len()
+        )?;
+        for desc in &self.missing {
+            write!(f, "\n  missing: {}_{}", desc.name, desc.crc)?;
+        }
+        for m in &self.crc_mismatch {
+            write!(
+                f,
+                "\n  crc mismatch: {}, generated: {}, vpp: {}",
+                m.name, m.expected, m.actual
+            )?;
+        }
+
+        Ok(())
+    }
+}
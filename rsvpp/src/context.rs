@@ -0,0 +1,106 @@
+use std::{
+    ops::RangeInclusive,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+use crate::Session;
+
+/// Lock-free allocator of request contexts
+///
+/// Contexts are allocated incrementally, skipping 0 and reserved ranges. Once the counter
+/// wrapped around, caller must skip contexts still outstanding in session as well.
+pub(crate) struct ContextAllocator {
+    next: AtomicU32,
+    wrapped: AtomicBool,
+    reserved: Vec<RangeInclusive<u32>>,
+}
+
+impl ContextAllocator {
+    pub fn new(reserved: Vec<RangeInclusive<u32>>) -> Self {
+        Self {
+            next: AtomicU32::new(0),
+            wrapped: AtomicBool::new(false),
+            reserved,
+        }
+    }
+
+    /// Next context which is neither 0 nor reserved, and whether the counter has wrapped
+    pub fn next(&self) -> (u32, bool) {
+        loop {
+            let prev = self.next.fetch_add(1, Ordering::Relaxed);
+            if prev == u32::MAX {
+                log::debug!("Context wrapped around");
+                self.wrapped.store(true, Ordering::Relaxed);
+            }
+
+            let ctx = prev.wrapping_add(1);
+            if ctx == 0 || self.is_reserved(ctx) {
+                continue;
+            }
+
+            return (ctx, self.wrapped.load(Ordering::Relaxed));
+        }
+    }
+
+    /// Next context which is not outstanding in session
+    pub async fn next_free(&self, sess: &Session) -> u32 {
+        loop {
+            let (ctx, wrapped) = self.next();
+
+            // Contexts allocated before wrapping may be still in use
+            if !wrapped || !sess.is_outstanding(ctx).await {
+                return ctx;
+            }
+        }
+    }
+
+    pub fn is_reserved(&self, ctx: u32) -> bool {
+        self.reserved.iter().any(|range| range.contains(&ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_zero() {
+        let alloc = ContextAllocator::new(Vec::new());
+        alloc.next.store(u32::MAX - 1, Ordering::Relaxed);
+
+        assert_eq!(alloc.next(), (u32::MAX, false));
+        assert_eq!(alloc.next(), (1, true));
+    }
+
+    #[test]
+    fn skip_reserved() {
+        let alloc = ContextAllocator::new(vec![2..=4, 6..=6]);
+
+        let ctxs = (0..4).map(|_| alloc.next().0).collect::<Vec<_>>();
+        assert_eq!(ctxs, vec![1, 5, 7, 8]);
+    }
+
+    #[test]
+    fn skip_outstanding_after_wrap() {
+        crate::runtime::block_on(async {
+            let (stream, _peer) = std::os::unix::net::UnixStream::pair().unwrap();
+            let sess = Session::new(crate::transport::unix_from_std(stream).unwrap());
+            let frame = || crate::PendingFrame {
+                name: "control_ping",
+                crc: "51077d14",
+                buf: Vec::new(),
+            };
+            sess.send_requests(vec![(1, frame()), (3, frame())], 1000)
+                .await
+                .unwrap();
+
+            // Outstanding contexts are only checked once wrapped
+            let alloc = ContextAllocator::new(Vec::new());
+            assert_eq!(alloc.next_free(&sess).await, 1);
+
+            alloc.next.store(u32::MAX, Ordering::Relaxed);
+            assert_eq!(alloc.next_free(&sess).await, 2);
+            assert_eq!(alloc.next_free(&sess).await, 4);
+        })
+    }
+}
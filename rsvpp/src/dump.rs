@@ -0,0 +1,239 @@
+use std::{
+    collections::VecDeque,
+    marker::PhantomData,
+    ops::Deref,
+    pin::Pin,
+    sync::{atomic::Ordering, Arc},
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures::{future::BoxFuture, Stream};
+
+use crate::{
+    api_span::{self, ApiSpan},
+    hard_coded_message::{VlApiControlPingReplyT, VlApiControlPingT},
+    message::{MessageCrc, MessageName},
+    pack::Pack,
+    Client, Error, RecvEntry, Result,
+};
+
+pub(crate) enum ClientRef<'a> {
+    Borrowed(&'a Client),
+    Shared(Arc<Client>),
+}
+
+impl<'a> Deref for ClientRef<'a> {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            ClientRef::Borrowed(client) => client,
+            ClientRef::Shared(client) => client,
+        }
+    }
+}
+
+/// Stream of `_details` replies, terminated by the `control_ping_reply` sent by `Client::dump`
+///
+/// Dropping the stream early discards the remaining replies of the dump.
+pub struct Dump<'a, T> {
+    client: ClientRef<'a>,
+    ctx: u32,
+    deadline: Option<Instant>,
+    entries: VecDeque<RecvEntry>,
+    recv: Option<BoxFuture<'a, Result<Vec<RecvEntry>>>>,
+    finished: bool,
+    failed: bool,
+    request: &'static str,
+    started: Instant,
+    span: ApiSpan,
+    replies: usize,
+    /// Ids of `control_ping_reply` and `T`, resolved by first reply
+    msg_ids: Option<(u16, u16)>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> Dump<'a, T>
+where
+    T: Pack + MessageName + MessageCrc,
+{
+    pub(crate) fn new(
+        client: ClientRef<'a>,
+        ctx: u32,
+        deadline: Option<Instant>,
+        request: &'static str,
+        started: Instant,
+        span: ApiSpan,
+    ) -> Self {
+        client.in_flight_counter().fetch_add(1, Ordering::SeqCst);
+
+        Self {
+            client,
+            ctx,
+            deadline,
+            entries: VecDeque::new(),
+            recv: None,
+            finished: false,
+            failed: false,
+            request,
+            started,
+            span,
+            replies: 0,
+            msg_ids: None,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn context(&self) -> u32 {
+        self.ctx
+    }
+
+    fn record(&self, res: std::result::Result<(), &Error>) {
+        match res {
+            Ok(_) => {
+                let _entered = self.span.enter();
+                api_span::record_replies(self.replies);
+            }
+            Err(e) => self.span.record_error(e),
+        }
+        self.client
+            .metrics_recorder()
+            .complete(self.request, self.started, res);
+    }
+
+    fn decode(&mut self, entry: RecvEntry) -> Result<Option<T>> {
+        let msg_id = entry.header._vl_msg_id;
+        let (ping_reply_id, details_id) = match self.msg_ids {
+            Some(ids) => ids,
+            None => {
+                let ids = (
+                    self.client.get_msg_id::<VlApiControlPingReplyT>()?,
+                    self.client.get_msg_id::<T>()?,
+                );
+                self.msg_ids = Some(ids);
+                ids
+            }
+        };
+
+        // Control ping reply is the end of dump
+        if msg_id == ping_reply_id {
+            self.finished = true;
+            let rep = VlApiControlPingReplyT::unpack(&entry.data, 0)?.0;
+            {
+                let _entered = self.span.enter();
+                api_span::record_retval(rep.retval);
+            }
+            if rep.retval != 0 {
+                return Err(Error::vpp_api(
+                    rep.retval,
+                    None,
+                    VlApiControlPingT::message_name(),
+                ));
+            }
+
+            return Ok(None);
+        }
+
+        if msg_id != details_id {
+            return Err(Error::msg_id_mismatch(format!(
+                "Message id mismatch in dump of '{}', received {}",
+                T::message_name(),
+                msg_id
+            )));
+        }
+
+        Ok(Some(T::unpack(&entry.data, 0)?.0))
+    }
+}
+
+impl<'a, T> Stream for Dump<'a, T>
+where
+    T: Pack + MessageName + MessageCrc,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished || self.failed {
+                return Poll::Ready(None);
+            }
+
+            // Decode received entries
+            if let Some(entry) = self.entries.pop_front() {
+                match self.decode(entry) {
+                    Ok(Some(msg)) => {
+                        self.replies += 1;
+                        return Poll::Ready(Some(Ok(msg)));
+                    }
+                    Ok(None) => {
+                        self.client.session().release_now(self.ctx);
+                        self.record(Ok(()));
+                        return Poll::Ready(None);
+                    }
+                    Err(e) => {
+                        self.failed = true;
+                        self.client.session().release_now(self.ctx);
+                        self.record(Err(&e));
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+            }
+
+            // Receive more entries
+            if self.recv.is_none() {
+                let ctx = self.ctx;
+                let deadline = self.deadline;
+                self.recv = Some(match &self.client {
+                    ClientRef::Borrowed(client) => Box::pin(client.recv_until(ctx, deadline)),
+                    ClientRef::Shared(client) => {
+                        let client = client.clone();
+                        Box::pin(async move { client.recv_until(ctx, deadline).await })
+                    }
+                });
+            }
+            let recv = self.recv.as_mut().unwrap();
+            match recv.as_mut().poll(cx) {
+                Poll::Ready(Ok(entries)) => {
+                    self.recv = None;
+                    self.entries.extend(entries);
+                }
+                Poll::Ready(Err(e)) => {
+                    self.recv = None;
+                    self.failed = true;
+                    self.client.session().release_now(self.ctx);
+                    self.record(Err(&e));
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<'a, T> Drop for Dump<'a, T> {
+    fn drop(&mut self) {
+        self.client
+            .in_flight_counter()
+            .fetch_sub(1, Ordering::SeqCst);
+        // Released by `poll_next` on the last reply
+        if self.finished {
+            return;
+        }
+
+        // Remaining replies will still arrive, don't leave them in cache
+        let msg_id = match self.client.get_msg_id::<VlApiControlPingReplyT>() {
+            Ok(msg_id) => msg_id,
+            Err(e) => {
+                log::warn!("Discard dump of ctx {} error: {}", self.ctx, e);
+                self.client.session().release_now(self.ctx);
+                return;
+            }
+        };
+        if self.entries.iter().all(|e| e.header._vl_msg_id != msg_id) {
+            self.client.session().discard(self.ctx, msg_id);
+        } else {
+            self.client.session().release_now(self.ctx);
+        }
+    }
+}
@@ -20,10 +20,24 @@ pub enum Error {
     MsgIdMismatch(String),
     #[error("Crc id mismatch: `{0}`")]
     CrcMismatch(String),
-    #[error("App api error: `{0}`")]
-    VppApi(String),
+    /// Non zero `retval` of a reply, match it by `error_map::VppErrno::of(&err)` of generated code
+    #[error("App api error: `{message_name}` returned {code}{}", .errno.map(|e| format!(", {}", e)).unwrap_or_default())]
+    VppApi {
+        code: i32,
+        /// Name and message of `code` for display, `None` for code unknown to generated code
+        errno: Option<VppErrno>,
+        message_name: String,
+    },
+    #[error("Decode error: `{0}`")]
+    Decode(pack::Error),
+    #[error("Encode error: `{0}`")]
+    Encode(pack::Error),
     #[error("Timeout error: `{0}`")]
     Timeout(String),
+    #[error("Disconnected error: `{0}`")]
+    Disconnected(String),
+    #[error("Closed error: `{0}`")]
+    Closed(String),
 }
 
 impl Error {
@@ -31,8 +45,38 @@ impl Error {
     lazy_error_impl! {argument, Argument}
     lazy_error_impl! {msg_id_mismatch, MsgIdMismatch}
     lazy_error_impl! {crc_mismatch, CrcMismatch}
-    lazy_error_impl! {vpp_api, VppApi}
     lazy_error_impl! {timeout, Timeout}
+    lazy_error_impl! {disconnected, Disconnected}
+    lazy_error_impl! {closed, Closed}
+
+    /// Non zero `retval` of message `message_name`
+    pub fn vpp_api<S: Into<String>>(code: i32, errno: Option<VppErrno>, message_name: S) -> Self {
+        Self::VppApi {
+            code,
+            errno,
+            message_name: message_name.into(),
+        }
+    }
+}
+
+/// Entry of vpp `api_errno.h`, e.g. `NO_SUCH_ENTRY`, the typed one is `error_map::VppErrno`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VppErrno {
+    pub name: &'static str,
+    pub message: &'static str,
+}
+
+impl std::fmt::Display for VppErrno {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: '{}'", self.name, self.message)
+    }
+}
+
+/// Pack errors raised by `?` are from unpack, encoding maps them to `Encode` explicitly
+impl From<pack::Error> for Error {
+    fn from(e: pack::Error) -> Self {
+        Self::Decode(e)
+    }
 }
 
 impl From<Box<dyn std::error::Error>> for Error {
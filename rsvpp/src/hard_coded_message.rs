@@ -1,12 +1,13 @@
 use crate::{
-    message::{MessageClientId, MessageContext, MessageId, MessageName},
+    message::{MessageClientId, MessageContext, MessageCrc, MessageId, MessageName, Request},
     pack::Pack,
+    Error, Result,
 };
 
 pub const VL_API_SOCK_CLNT_CREATE_MSG_ID: u16 = 15;
 pub const VL_API_SOCK_CLNT_CREATE_REP_MSG_ID: u16 = 16;
 
-#[derive(Pack, Debug, PartialEq, Eq)]
+#[derive(Pack, Debug, Clone, PartialEq, Eq)]
 #[packed]
 pub struct ApiMessageReplyHeader {
     pub _vl_msg_id: u16,
@@ -23,8 +24,8 @@ pub struct VlApiSockclntCreateT {
 }
 
 impl MessageName for VlApiSockclntCreateT {
-    fn message_name() -> String {
-        "sockclnt_create".to_string()
+    fn message_name() -> &'static str {
+        "sockclnt_create"
     }
 }
 
@@ -64,8 +65,8 @@ pub struct VlApiSockclntCreateReplyT {
 }
 
 impl MessageName for VlApiSockclntCreateReplyT {
-    fn message_name() -> String {
-        "sockclnt_create_reply".to_string()
+    fn message_name() -> &'static str {
+        "sockclnt_create_reply"
     }
 }
 
@@ -109,3 +110,328 @@ pub struct VlApiMessageTableEntryT {
     #[len(64)]
     pub name: String,
 }
+
+#[derive(Pack, Debug, PartialEq, Eq, Default)]
+#[packed]
+pub struct VlApiSockclntDeleteT {
+    pub _vl_msg_id: u16,
+    pub client_index: u32,
+    pub context: u32,
+    pub index: u32,
+}
+
+impl MessageName for VlApiSockclntDeleteT {
+    fn message_name() -> &'static str {
+        "sockclnt_delete"
+    }
+}
+
+impl MessageCrc for VlApiSockclntDeleteT {
+    fn crc() -> &'static str {
+        "8ac76db6"
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq)]
+#[packed]
+pub struct VlApiSockclntDeleteReplyT {
+    pub _vl_msg_id: u16,
+    pub context: u32,
+    pub response: i32,
+}
+
+impl MessageName for VlApiSockclntDeleteReplyT {
+    fn message_name() -> &'static str {
+        "sockclnt_delete_reply"
+    }
+}
+
+impl MessageCrc for VlApiSockclntDeleteReplyT {
+    fn crc() -> &'static str {
+        "8f38b1ee"
+    }
+}
+
+impl MessageId for VlApiSockclntDeleteReplyT {
+    fn message_id(&self) -> u16 {
+        self._vl_msg_id
+    }
+
+    fn set_message_id(mut self, id: u16) -> Self {
+        self._vl_msg_id = id;
+        self
+    }
+}
+
+impl MessageContext for VlApiSockclntDeleteReplyT {
+    fn context(&self) -> u32 {
+        self.context
+    }
+
+    fn set_context(mut self, ctx: u32) -> Self {
+        self.context = ctx;
+        self
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq, Default)]
+#[packed]
+pub struct VlApiControlPingT {
+    pub _vl_msg_id: u16,
+    pub client_index: u32,
+    pub context: u32,
+}
+
+impl MessageName for VlApiControlPingT {
+    fn message_name() -> &'static str {
+        "control_ping"
+    }
+}
+
+impl MessageCrc for VlApiControlPingT {
+    fn crc() -> &'static str {
+        "51077d14"
+    }
+}
+
+impl MessageId for VlApiControlPingT {
+    fn message_id(&self) -> u16 {
+        self._vl_msg_id
+    }
+
+    fn set_message_id(mut self, id: u16) -> Self {
+        self._vl_msg_id = id;
+        self
+    }
+}
+
+impl MessageContext for VlApiControlPingT {
+    fn context(&self) -> u32 {
+        self.context
+    }
+
+    fn set_context(mut self, ctx: u32) -> Self {
+        self.context = ctx;
+        self
+    }
+}
+
+impl MessageClientId for VlApiControlPingT {
+    fn client_index(&self) -> u32 {
+        self.client_index
+    }
+
+    fn set_client_index(mut self, idx: u32) -> Self {
+        self.client_index = idx;
+        self
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq)]
+#[packed]
+pub struct VlApiControlPingReplyT {
+    pub _vl_msg_id: u16,
+    pub context: u32,
+    pub retval: i32,
+    pub client_index: u32,
+    pub vpe_pid: u32,
+}
+
+impl MessageName for VlApiControlPingReplyT {
+    fn message_name() -> &'static str {
+        "control_ping_reply"
+    }
+}
+
+impl MessageCrc for VlApiControlPingReplyT {
+    fn crc() -> &'static str {
+        "f6b0b8ca"
+    }
+}
+
+impl MessageId for VlApiControlPingReplyT {
+    fn message_id(&self) -> u16 {
+        self._vl_msg_id
+    }
+
+    fn set_message_id(mut self, id: u16) -> Self {
+        self._vl_msg_id = id;
+        self
+    }
+}
+
+impl MessageContext for VlApiControlPingReplyT {
+    fn context(&self) -> u32 {
+        self.context
+    }
+
+    fn set_context(mut self, ctx: u32) -> Self {
+        self.context = ctx;
+        self
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq, Default)]
+#[packed]
+pub struct VlApiApiVersionsT {
+    pub _vl_msg_id: u16,
+    pub client_index: u32,
+    pub context: u32,
+}
+
+impl MessageName for VlApiApiVersionsT {
+    fn message_name() -> &'static str {
+        "api_versions"
+    }
+}
+
+impl MessageCrc for VlApiApiVersionsT {
+    fn crc() -> &'static str {
+        "51077d14"
+    }
+}
+
+impl MessageId for VlApiApiVersionsT {
+    fn message_id(&self) -> u16 {
+        self._vl_msg_id
+    }
+
+    fn set_message_id(mut self, id: u16) -> Self {
+        self._vl_msg_id = id;
+        self
+    }
+}
+
+impl MessageContext for VlApiApiVersionsT {
+    fn context(&self) -> u32 {
+        self.context
+    }
+
+    fn set_context(mut self, ctx: u32) -> Self {
+        self.context = ctx;
+        self
+    }
+}
+
+impl MessageClientId for VlApiApiVersionsT {
+    fn client_index(&self) -> u32 {
+        self.client_index
+    }
+
+    fn set_client_index(mut self, idx: u32) -> Self {
+        self.client_index = idx;
+        self
+    }
+}
+
+impl Request for VlApiApiVersionsT {
+    type Reply = VlApiApiVersionsReplyT;
+    const IS_STREAM: bool = false;
+
+    fn check_reply(reply: &Self::Reply) -> Result<()> {
+        if reply.retval != 0 {
+            return Err(Error::vpp_api(
+                reply.retval,
+                None,
+                VlApiApiVersionsT::message_name(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq)]
+#[packed]
+pub struct VlApiApiVersionsReplyT {
+    pub _vl_msg_id: u16,
+    pub context: u32,
+    pub retval: i32,
+    pub count: u32,
+    #[len("count")]
+    pub api_versions: Vec<VlApiModuleVersionT>,
+}
+
+impl MessageName for VlApiApiVersionsReplyT {
+    fn message_name() -> &'static str {
+        "api_versions_reply"
+    }
+}
+
+impl MessageCrc for VlApiApiVersionsReplyT {
+    fn crc() -> &'static str {
+        "5f0d99d6"
+    }
+}
+
+impl MessageId for VlApiApiVersionsReplyT {
+    fn message_id(&self) -> u16 {
+        self._vl_msg_id
+    }
+
+    fn set_message_id(mut self, id: u16) -> Self {
+        self._vl_msg_id = id;
+        self
+    }
+}
+
+impl MessageContext for VlApiApiVersionsReplyT {
+    fn context(&self) -> u32 {
+        self.context
+    }
+
+    fn set_context(mut self, ctx: u32) -> Self {
+        self.context = ctx;
+        self
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq)]
+#[packed]
+pub struct VlApiModuleVersionT {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    #[len(64)]
+    pub name: String,
+}
+
+#[derive(Pack, Debug, PartialEq, Eq, Default)]
+#[packed]
+pub struct VlApiMemclntKeepaliveT {
+    pub _vl_msg_id: u16,
+    pub client_index: u32,
+    pub context: u32,
+}
+
+impl MessageName for VlApiMemclntKeepaliveT {
+    fn message_name() -> &'static str {
+        "memclnt_keepalive"
+    }
+}
+
+impl MessageCrc for VlApiMemclntKeepaliveT {
+    fn crc() -> &'static str {
+        "51077d14"
+    }
+}
+
+#[derive(Pack, Debug, PartialEq, Eq, Default)]
+#[packed]
+pub struct VlApiMemclntKeepaliveReplyT {
+    pub _vl_msg_id: u16,
+    pub context: u32,
+    pub retval: i32,
+}
+
+impl MessageName for VlApiMemclntKeepaliveReplyT {
+    fn message_name() -> &'static str {
+        "memclnt_keepalive_reply"
+    }
+}
+
+impl MessageCrc for VlApiMemclntKeepaliveReplyT {
+    fn crc() -> &'static str {
+        "e8d4e804"
+    }
+}
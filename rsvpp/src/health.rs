@@ -0,0 +1,147 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use crate::{
+    hard_coded_message::{VlApiControlPingReplyT, VlApiControlPingT},
+    message::{Message, MessageCrc, MessageName},
+    reconnect::Reconnector,
+    runtime, Error, Result,
+};
+
+/// Background `control_ping` of client, see `ClientBuilder::health_check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCheck {
+    /// Interval between pings in milliseconds
+    pub interval: u64,
+    /// Timeout of each ping in milliseconds
+    pub timeout: u64,
+    /// Consecutive failed pings before connection is flagged unhealthy
+    pub max_failures: usize,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            interval: 5 * 1000,
+            timeout: 3 * 1000,
+            max_failures: 1,
+        }
+    }
+}
+
+pub(crate) struct HealthTask {
+    conn: Weak<Reconnector>,
+    healthy: Arc<AtomicBool>,
+    config: HealthCheck,
+}
+
+impl HealthTask {
+    pub fn start(conn: Weak<Reconnector>, healthy: Arc<AtomicBool>, config: HealthCheck) {
+        let instance = Self {
+            conn,
+            healthy,
+            config,
+        };
+
+        runtime::spawn(async move {
+            instance.run().await;
+        });
+    }
+
+    async fn run(&self) {
+        let mut failures = 0;
+        loop {
+            runtime::sleep(Duration::from_millis(self.config.interval)).await;
+
+            // Quit after client dropped
+            let conn = match self.conn.upgrade() {
+                Some(conn) => conn,
+                None => break,
+            };
+
+            match self.ping(&conn).await {
+                Ok(_) => {
+                    failures = 0;
+                    if !self.healthy.swap(true, Ordering::SeqCst) {
+                        log::info!("Health check recovered");
+                    }
+                }
+                Err(Error::Closed(_)) => break,
+                Err(e) => {
+                    failures += 1;
+                    log::warn!("Health check failed {} times: {}", failures, e);
+                    if failures >= self.config.max_failures
+                        && self.healthy.swap(false, Ordering::SeqCst)
+                    {
+                        log::warn!("Connection unhealthy");
+                    }
+
+                    // Reopen lost transport, no request may come to trigger it
+                    if conn.policy().enabled {
+                        if let Err(e) = conn.ensure_connected().await {
+                            log::warn!("Health check reconnect error: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+
+        log::debug!("Quit HealthTask");
+    }
+
+    async fn ping(&self, conn: &Reconnector) -> Result<()> {
+        let sess = conn.session();
+        let (client_index, msg_id, reply_id) = {
+            let reg = conn.reg();
+            (
+                reg.client_index(),
+                reg.lookup_type::<VlApiControlPingT>()?,
+                reg.lookup_type::<VlApiControlPingReplyT>()?,
+            )
+        };
+
+        // Send ping
+        let ctx = conn.next_ctx().await;
+        let msg = Message::new(VlApiControlPingT {
+            _vl_msg_id: msg_id,
+            client_index,
+            context: ctx,
+        });
+        sess.send_request(
+            ctx,
+            VlApiControlPingT::message_name(),
+            VlApiControlPingT::crc(),
+            msg,
+            self.config.timeout,
+        )
+        .await?;
+
+        // Wait reply
+        let rep = sess
+            .recv_single_msg::<VlApiControlPingReplyT>(ctx, reply_id, self.config.timeout)
+            .await;
+        match rep {
+            Ok(rep) => {
+                sess.release(ctx).await;
+                if rep.retval != 0 {
+                    return Err(Error::vpp_api(
+                        rep.retval,
+                        None,
+                        VlApiControlPingT::message_name(),
+                    ));
+                }
+
+                Ok(())
+            }
+            Err(e) => {
+                sess.discard(ctx, reply_id);
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,18 +1,66 @@
+//! Client of the vpp binary api, messages and services are generated by `rsvpp-apigen`
+//!
+//! # Errors
+//!
+//! A reply with non zero `retval` fails with [`Error::VppApi`]. Its `errno` only carries
+//! the name and message for display, match specific failures with the typed
+//! `VppErrno` enum generated into `error_map` from `api_errno.h`:
+//!
+//! ```ignore
+//! use vpp_api::error_map::VppErrno;
+//!
+//! match client.call(req).await {
+//!     Err(e) if VppErrno::of(&e) == Some(VppErrno::EntryAlreadyExists) => Ok(()),
+//!     res => res.map(|_| ()),
+//! }
+//! ```
+
+mod api_span;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
 mod client;
+mod compatibility;
+mod context;
+mod dump;
 mod error;
 mod hard_coded_message;
+mod health;
 pub mod message;
+mod metrics;
+mod options;
+mod pool;
+mod reconnect;
+mod retry;
+mod runtime;
 mod session;
+mod signal;
 mod stats;
-mod transport;
+mod subscription;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transport;
+mod wire_trace;
 
-pub use client::Client;
-pub use error::Error;
+pub use builder::ClientBuilder;
+pub use client::{Client, MessageEntry, ModuleVersion};
+pub use compatibility::{CompatibilityReport, CrcMismatch};
+pub use dump::Dump;
+pub use error::{Error, VppErrno};
+pub use futures;
+pub use health::HealthCheck;
 pub use lazy_static;
+pub use metrics::{LatencyHistogram, MessageMetrics, Metrics, SessionMetrics};
+pub use options::{BatchErrorMode, BatchOptions, CallOptions};
 pub use pack;
-pub use session::{RecvEntry, Session};
+pub use pool::{ClientPool, PoolStrategy, ServiceClient};
+pub use reconnect::{InFlightPolicy, ReconnectPolicy};
+pub use retry::RetryPolicy;
+pub use session::{CacheConfig, PendingFrame, RecvEntry, Session};
 pub use stats::Stats;
+pub use subscription::Subscription;
 pub use transport::Transport;
+pub use wire_trace::WireTrace;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -1,9 +1,10 @@
 use std::ops::{Deref, DerefMut};
 
-use crate::{pack::Pack, Result};
+use crate::{pack::Pack, Error, Result};
 
-pub trait MessageName {
-    fn message_name() -> String;
+/// Messages are owned types, their ids are cached by `TypeId` per connection
+pub trait MessageName: 'static {
+    fn message_name() -> &'static str;
 }
 
 pub trait MessageId {
@@ -25,6 +26,31 @@ pub trait MessageCrc {
     fn crc() -> &'static str;
 }
 
+/// Name and crc of a generated message, see `Client::compatibility_report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageDesc {
+    pub name: &'static str,
+    pub crc: &'static str,
+}
+
+/// Request message with its reply type, generated from the `services` section of api.json
+pub trait Request:
+    Pack + MessageName + MessageId + MessageContext + MessageClientId + MessageCrc
+{
+    type Reply: Pack + MessageName + MessageId + MessageContext + MessageCrc;
+
+    /// Replies are `_details` messages which must be received by `Client::dump`
+    const IS_STREAM: bool;
+
+    /// Sending again has no further effect, so it's retried by the client `RetryPolicy`
+    const IDEMPOTENT: bool = false;
+
+    /// Check reply content, e.g. `retval`
+    fn check_reply(_reply: &Self::Reply) -> Result<()> {
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Pack)]
 pub struct MessageHeader {
     pub q: u64,
@@ -38,7 +64,7 @@ impl MessageHeader {
     }
 
     pub fn encode(&mut self) -> Result<Vec<u8>> {
-        Ok(self.pack_vec()?)
+        self.pack_vec().map_err(Error::Encode)
     }
 
     pub fn decode(buf: &[u8]) -> Result<Self> {
@@ -61,7 +87,7 @@ impl<T: Pack> Message<T> {
 
     pub fn encode(&mut self) -> Result<Vec<u8>> {
         // Encode inner
-        let inner_buf = self._inner.pack_vec()?;
+        let inner_buf = self._inner.pack_vec().map_err(Error::Encode)?;
         let len = inner_buf.len();
 
         // Encode header
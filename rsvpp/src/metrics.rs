@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::Error;
+
+/// Upper bounds of latency buckets, the last bucket is unbounded
+const LATENCY_BOUNDS_US: &[u64] = &[
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000,
+];
+
+/// Snapshot of client metrics, see `Client::metrics`
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// Keyed by request message name
+    pub messages: HashMap<String, MessageMetrics>,
+    pub session: SessionMetrics,
+}
+
+/// Calls of one request message by `call`, `send_many` and `dump`
+#[derive(Debug, Clone, Default)]
+pub struct MessageMetrics {
+    pub requests: u64,
+    pub timeouts: u64,
+    /// Replies rejected by `Request::check_reply`, e.g. non zero `retval`
+    pub retval_errors: u64,
+    /// Calls failed otherwise, e.g. transport lost
+    pub failures: u64,
+    /// Time from sending request to final reply, including `retval` errors
+    pub latency: LatencyHistogram,
+}
+
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    /// Count of each bucket with its upper bound, the last bound is `Duration::MAX`
+    pub buckets: Vec<(Duration, u64)>,
+    pub count: u64,
+    pub sum: Duration,
+    pub max: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        let buckets = LATENCY_BOUNDS_US
+            .iter()
+            .map(|us| Duration::from_micros(*us))
+            .chain(Some(Duration::MAX))
+            .map(|bound| (bound, 0))
+            .collect();
+
+        Self {
+            buckets,
+            count: 0,
+            sum: Duration::default(),
+            max: Duration::default(),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        if let Some(bucket) = self.buckets.iter_mut().find(|(bound, _)| latency <= *bound) {
+            bucket.1 += 1;
+        }
+        self.count += 1;
+        self.sum += latency;
+        self.max = self.max.max(latency);
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        Some(Duration::from_secs_f64(
+            self.sum.as_secs_f64() / self.count as f64,
+        ))
+    }
+
+    /// Upper bound of the bucket containing quantile `q` in `0.0..=1.0`
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((self.count as f64) * q.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bound, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return Some((*bound).min(self.max));
+            }
+        }
+
+        Some(self.max)
+    }
+}
+
+/// Recv cache and request tracking of `Session`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionMetrics {
+    /// Requests sent and waiting for reply
+    pub outstanding_contexts: usize,
+    /// Contexts with replies not received by caller yet
+    pub cache_contexts: usize,
+    pub cache_messages: usize,
+    pub gc_runs: u64,
+    /// Cached replies and tracked requests expired by gc
+    pub gc_evictions: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    RetvalError,
+    Timeout,
+    Failure,
+}
+
+impl Outcome {
+    fn of(res: std::result::Result<(), &Error>) -> Self {
+        match res {
+            Ok(_) => Outcome::Ok,
+            Err(Error::VppApi { .. }) => Outcome::RetvalError,
+            Err(Error::Timeout(_)) => Outcome::Timeout,
+            Err(_) => Outcome::Failure,
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn label(&self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::RetvalError => "retval_error",
+            Outcome::Timeout => "timeout",
+            Outcome::Failure => "failure",
+        }
+    }
+}
+
+/// Per message counters of a client, also exported to the `metrics` crate with feature `metrics`
+#[derive(Debug)]
+pub(crate) struct MetricsRecorder {
+    #[cfg(feature = "metrics")]
+    client: String,
+    messages: Mutex<HashMap<String, MessageMetrics>>,
+}
+
+impl MetricsRecorder {
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub fn new(client: &str) -> Self {
+        Self {
+            #[cfg(feature = "metrics")]
+            client: client.to_string(),
+            messages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn request(&self, name: &str) {
+        self.update(name, |m| m.requests += 1);
+
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!(
+            "rsvpp_requests_total",
+            "client" => self.client.clone(),
+            "message" => name.to_string()
+        )
+        .increment(1);
+    }
+
+    /// Outcome of a request sent at `started`
+    pub fn complete(&self, name: &str, started: Instant, res: std::result::Result<(), &Error>) {
+        let latency = started.elapsed();
+        let outcome = Outcome::of(res);
+
+        self.update(name, |m| match outcome {
+            Outcome::Ok => m.latency.record(latency),
+            Outcome::RetvalError => {
+                m.retval_errors += 1;
+                m.latency.record(latency);
+            }
+            Outcome::Timeout => m.timeouts += 1,
+            Outcome::Failure => m.failures += 1,
+        });
+
+        #[cfg(feature = "metrics")]
+        {
+            if matches!(outcome, Outcome::Ok | Outcome::RetvalError) {
+                ::metrics::histogram!(
+                    "rsvpp_request_duration_seconds",
+                    "client" => self.client.clone(),
+                    "message" => name.to_string()
+                )
+                .record(latency.as_secs_f64());
+            }
+            if outcome != Outcome::Ok {
+                ::metrics::counter!(
+                    "rsvpp_request_errors_total",
+                    "client" => self.client.clone(),
+                    "message" => name.to_string(),
+                    "kind" => outcome.label()
+                )
+                .increment(1);
+            }
+        }
+    }
+
+    pub fn messages(&self) -> HashMap<String, MessageMetrics> {
+        self.messages
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn update<F: FnOnce(&mut MessageMetrics)>(&self, name: &str, f: F) {
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        match messages.get_mut(name) {
+            Some(metrics) => f(metrics),
+            None => f(messages.entry(name.to_string()).or_default()),
+        }
+    }
+}
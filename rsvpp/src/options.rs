@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use crate::RetryPolicy;
+
+/// Options of a single call, see `Client::call_with` and `Client::dump_with`
+///
+/// Without timeout and deadline, each send and recv is limited by the client timeout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallOptions {
+    /// Timeout of the whole call in milliseconds
+    pub timeout: Option<u64>,
+    /// Deadline of the whole call, including all replies of a dump
+    pub deadline: Option<Instant>,
+    /// Overrides the client retry policy, also for requests not marked idempotent
+    pub retry: Option<RetryPolicy>,
+}
+
+impl CallOptions {
+    pub fn timeout(ms: u64) -> Self {
+        Self {
+            timeout: Some(ms),
+            ..Self::default()
+        }
+    }
+
+    pub fn deadline(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..Self::default()
+        }
+    }
+
+    pub fn retry(policy: RetryPolicy) -> Self {
+        Self {
+            retry: Some(policy),
+            ..Self::default()
+        }
+    }
+
+    /// The earlier one of deadline and timeout
+    pub(crate) fn resolve_deadline(&self) -> Option<Instant> {
+        let timeout = self
+            .timeout
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+
+        match (self.deadline, timeout) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchErrorMode {
+    /// Stop sending after the first failed reply, requests in flight are still received
+    StopOnError,
+    /// Send all requests and return every failure
+    CollectAll,
+}
+
+/// Options of `Client::send_many`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Max requests waiting for reply
+    pub window: usize,
+    pub error_mode: BatchErrorMode,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            window: 64,
+            error_mode: BatchErrorMode::StopOnError,
+        }
+    }
+}
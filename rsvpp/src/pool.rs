@@ -0,0 +1,170 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, RwLock,
+};
+
+use futures::lock::Mutex;
+
+use crate::{Client, ClientBuilder, Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolStrategy {
+    RoundRobin,
+    /// Member with the fewest calls waiting for reply
+    LeastInFlight,
+}
+
+/// Registered connections to one vpp
+pub struct ClientPool {
+    builder: ClientBuilder,
+    members: Vec<RwLock<Arc<Client>>>,
+    strategy: RwLock<PoolStrategy>,
+    next: AtomicUsize,
+    replace_lock: Mutex<()>,
+    closed: AtomicBool,
+}
+
+impl ClientPool {
+    pub async fn new(sock: &str, size: usize) -> Result<Self> {
+        Self::with_builder(ClientBuilder::new().socket(sock), size).await
+    }
+
+    /// Connect `size` clients by builder, failed members are replaced by new ones of it
+    pub async fn with_builder(builder: ClientBuilder, size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(Error::argument("Pool size must be greater than 0"));
+        }
+
+        let mut members = Vec::with_capacity(size);
+        for _ in 0..size {
+            let client = builder.clone().connect().await?;
+            members.push(RwLock::new(Arc::new(client)));
+        }
+
+        Ok(Self {
+            builder,
+            members,
+            strategy: RwLock::new(PoolStrategy::RoundRobin),
+            next: AtomicUsize::new(0),
+            replace_lock: Mutex::new(()),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Change how members are selected, takes effect from the next `get`
+    pub fn set_strategy(&self, strategy: PoolStrategy) {
+        *self.strategy.write().unwrap_or_else(|e| e.into_inner()) = strategy;
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Select a member, it's replaced first if unhealthy
+    pub async fn get(&self) -> Result<Arc<Client>> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::closed("Pool closed"));
+        }
+
+        let index = self.select();
+        let client = self.member(index);
+        if client.is_healthy() {
+            return Ok(client);
+        }
+
+        match self.replace(index).await {
+            Ok(client) => Ok(client),
+            Err(e) => {
+                log::warn!("Replace pool member {} error: {}", index, e);
+
+                // Fallback to other healthy member
+                (0..self.len())
+                    .map(|i| self.member(i))
+                    .find(|client| client.is_healthy())
+                    .ok_or(e)
+            }
+        }
+    }
+
+    /// Close all members
+    pub async fn close(&self) -> Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+
+        let mut res = Ok(());
+        for i in 0..self.len() {
+            if let Err(e) = self.member(i).close().await {
+                log::warn!("Close pool member {} error: {}", i, e);
+                res = Err(e);
+            }
+        }
+
+        res
+    }
+
+    fn select(&self) -> usize {
+        let strategy = *self.strategy.read().unwrap_or_else(|e| e.into_inner());
+        match strategy {
+            PoolStrategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.len(),
+            PoolStrategy::LeastInFlight => (0..self.len())
+                .min_by_key(|i| self.member(*i).in_flight())
+                .unwrap_or(0),
+        }
+    }
+
+    fn member(&self, index: usize) -> Arc<Client> {
+        self.members[index]
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    async fn replace(&self, index: usize) -> Result<Arc<Client>> {
+        let _guard = self.replace_lock.lock().await;
+
+        // Already replaced by other task
+        let client = self.member(index);
+        if client.is_healthy() {
+            return Ok(client);
+        }
+
+        log::info!("Replace unhealthy pool member {}", index);
+        let client = Arc::new(self.builder.clone().connect().await?);
+        *self.members[index]
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = client.clone();
+
+        Ok(client)
+    }
+}
+
+/// Client of generated services, either a single client or a pool
+#[derive(Clone)]
+pub enum ServiceClient {
+    Client(Arc<Client>),
+    Pool(Arc<ClientPool>),
+}
+
+impl ServiceClient {
+    pub async fn get(&self) -> Result<Arc<Client>> {
+        match self {
+            ServiceClient::Client(client) => Ok(client.clone()),
+            ServiceClient::Pool(pool) => pool.get().await,
+        }
+    }
+}
+
+impl From<Arc<Client>> for ServiceClient {
+    fn from(client: Arc<Client>) -> Self {
+        ServiceClient::Client(client)
+    }
+}
+
+impl From<Arc<ClientPool>> for ServiceClient {
+    fn from(pool: Arc<ClientPool>) -> Self {
+        ServiceClient::Pool(pool)
+    }
+}
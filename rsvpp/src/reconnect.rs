@@ -0,0 +1,353 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock, RwLockReadGuard, Weak,
+    },
+    time::Duration,
+};
+
+use futures::lock::Mutex;
+
+use crate::{
+    client::Registration,
+    context::ContextAllocator,
+    hard_coded_message::{
+        VlApiMemclntKeepaliveReplyT, VlApiMemclntKeepaliveT, VlApiSockclntCreateReplyT,
+        VlApiSockclntCreateT, VL_API_SOCK_CLNT_CREATE_MSG_ID, VL_API_SOCK_CLNT_CREATE_REP_MSG_ID,
+    },
+    message::{Message, MessageHeader},
+    pack::Pack,
+    runtime,
+    session::PendingFrame,
+    transport::Endpoint,
+    Client, Error, Result, Session,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// Requests without reply fail with `Error::Disconnected`
+    Fail,
+    /// Requests without reply are sent again after reconnecting
+    Retry,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Reconnect automatically after transport lost
+    pub enabled: bool,
+    /// Max connect attempts for each reconnect
+    pub max_attempts: usize,
+    /// Interval between attempts in milliseconds
+    pub interval: u64,
+    /// How to handle requests waiting for reply when transport lost
+    pub in_flight: InFlightPolicy,
+}
+
+impl ReconnectPolicy {
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 10,
+            interval: 1000,
+            in_flight: InFlightPolicy::Fail,
+        }
+    }
+}
+
+/// Request enabling events of a subscription, e.g. `want_interface_events`
+struct Enable {
+    frame: PendingFrame,
+    /// Dead once the subscription dropped
+    alive: Weak<()>,
+}
+
+/// Connection of a client, shared with `HealthTask` to reconnect in background
+pub(crate) struct Reconnector {
+    endpoint: Endpoint,
+    name: String,
+    sess: Arc<Session>,
+    ctx: Arc<ContextAllocator>,
+    reg: Arc<RwLock<Registration>>,
+    timeout: Arc<AtomicU64>,
+    connect_timeout: u64,
+    policy: RwLock<ReconnectPolicy>,
+    lock: Mutex<()>,
+    enables: std::sync::Mutex<Vec<Enable>>,
+}
+
+impl Reconnector {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        endpoint: Endpoint,
+        name: &str,
+        sess: Arc<Session>,
+        ctx: Arc<ContextAllocator>,
+        reg: Arc<RwLock<Registration>>,
+        timeout: Arc<AtomicU64>,
+        connect_timeout: u64,
+        policy: ReconnectPolicy,
+    ) -> Self {
+        Self {
+            endpoint,
+            name: name.to_string(),
+            sess,
+            ctx,
+            reg,
+            timeout,
+            connect_timeout,
+            policy: RwLock::new(policy),
+            lock: Mutex::new(()),
+            enables: std::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn session(&self) -> &Arc<Session> {
+        &self.sess
+    }
+
+    pub fn contexts(&self) -> &ContextAllocator {
+        &self.ctx
+    }
+
+    pub fn reg(&self) -> RwLockReadGuard<'_, Registration> {
+        self.reg.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn policy(&self) -> ReconnectPolicy {
+        self.policy
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_policy(&self, policy: ReconnectPolicy) {
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Whether the registration is of the current transport
+    pub fn is_connected(&self) -> bool {
+        self.reg().epoch() == self.sess.epoch()
+    }
+
+    /// Send `frame` again after each reconnect while the returned token is alive
+    pub fn add_enable(&self, frame: PendingFrame) -> Arc<()> {
+        let token = Arc::new(());
+        let mut enables = self.enables.lock().unwrap_or_else(|e| e.into_inner());
+        enables.retain(|enable| enable.alive.strong_count() > 0);
+        enables.push(Enable {
+            frame,
+            alive: Arc::downgrade(&token),
+        });
+
+        token
+    }
+
+    pub async fn next_ctx(&self) -> u32 {
+        self.ctx.next_free(&self.sess).await
+    }
+
+    fn timeout(&self) -> u64 {
+        self.timeout.load(Ordering::SeqCst)
+    }
+
+    pub async fn init(&self) -> Result<()> {
+        log::trace!("Init client");
+
+        let epoch = self.sess.epoch();
+        let ctx = self.next_ctx().await;
+
+        // Send socket client init message
+        let sock_clnt_create_msg = Message::new(VlApiSockclntCreateT {
+            _vl_msg_id: VL_API_SOCK_CLNT_CREATE_MSG_ID,
+            context: ctx,
+            name: self.name.clone(),
+        });
+        log::trace!("Send sockclnt create");
+        self.sess
+            .send_msg(sock_clnt_create_msg, self.timeout())
+            .await?;
+
+        // Get vpp msg table
+        // XXX: sockclnt reply context is 0
+        log::trace!("Wait sockclnt create reply");
+        let sock_clnt_rep_msg: VlApiSockclntCreateReplyT = self
+            .sess
+            .recv_single_msg(0, VL_API_SOCK_CLNT_CREATE_REP_MSG_ID, self.timeout())
+            .await?;
+
+        // Update client index & init hash
+        log::trace!("Client index: {}", sock_clnt_rep_msg.index);
+        let reg = Registration::new(epoch, &sock_clnt_rep_msg)?;
+        if let Some(trace) = self.sess.wire_trace() {
+            trace.set_names(reg.message_names());
+        }
+
+        // Answer keepalive of vpp
+        match (
+            reg.lookup_type::<VlApiMemclntKeepaliveT>(),
+            reg.lookup_type::<VlApiMemclntKeepaliveReplyT>(),
+        ) {
+            (Ok(msg_id), Ok(reply_id)) => self.sess.set_keepalive(msg_id, reply_id).await,
+            (Err(e), _) | (_, Err(e)) => log::warn!("Keepalive not supported: {}", e),
+        }
+
+        *self.reg.write().unwrap_or_else(|e| e.into_inner()) = reg;
+
+        Ok(())
+    }
+
+    pub async fn ensure_connected(&self) -> Result<()> {
+        if self.is_connected() {
+            return Ok(());
+        }
+
+        if !self.policy().enabled {
+            return Err(Error::disconnected("Transport lost"));
+        }
+
+        self.reconnect().await
+    }
+
+    pub async fn reconnect(&self) -> Result<()> {
+        let _guard = self.lock.lock().await;
+
+        // Already reconnected by other task
+        if self.is_connected() {
+            return Ok(());
+        }
+
+        if !self.endpoint.can_reconnect() {
+            return Err(Error::disconnected(format!(
+                "Transport lost, {} transport can't be reopened",
+                self.endpoint
+            )));
+        }
+
+        let policy = self.policy();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            log::info!("Reconnect '{}', attempt {}", self.endpoint, attempt);
+
+            match Client::with_timeout(self.connect_timeout, "Reconnect", self.try_reconnect())
+                .await
+            {
+                Ok(_) => break,
+                Err(e) if attempt < policy.max_attempts => {
+                    log::warn!("Reconnect '{}' error: {}", self.endpoint, e);
+                    runtime::sleep(Duration::from_millis(policy.interval)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Resend requests without reply
+        if policy.in_flight == InFlightPolicy::Retry {
+            self.sess
+                .retry_lost(|frame| self.patch_frame(frame), self.timeout())
+                .await?;
+        }
+
+        // Events are only sent to the client which enabled them
+        self.replay_enables().await;
+
+        Ok(())
+    }
+
+    async fn try_reconnect(&self) -> Result<()> {
+        let trans = self.endpoint.connect().await?;
+        self.sess.set_transport(trans).await;
+        self.init().await?;
+
+        // Message ids may change after vpp restarted
+        self.sess
+            .remap_subscribers(|name, crc| self.reg().lookup(name, crc))
+            .await;
+
+        Ok(())
+    }
+
+    async fn replay_enables(&self) {
+        let frames = {
+            let mut enables = self.enables.lock().unwrap_or_else(|e| e.into_inner());
+            enables.retain(|enable| enable.alive.strong_count() > 0);
+            enables
+                .iter()
+                .map(|enable| enable.frame.clone())
+                .collect::<Vec<_>>()
+        };
+
+        for mut frame in frames {
+            let name = frame.name;
+            if let Err(e) = self.replay_enable(&mut frame).await {
+                log::warn!("Enable '{}' after reconnect error: {}", name, e);
+            }
+        }
+    }
+
+    async fn replay_enable(&self, frame: &mut PendingFrame) -> Result<()> {
+        let ctx = self.next_ctx().await;
+        self.patch_frame(frame)?;
+        self.patch_ctx(frame, ctx)?;
+        log::debug!("Enable '{}' again with ctx {}", frame.name, ctx);
+
+        self.sess
+            .send_requests(vec![(ctx, frame.clone())], self.timeout())
+            .await?;
+        let res = self.sess.recv(ctx, self.timeout()).await;
+        self.sess.release(ctx).await;
+
+        // Replies start with msg id and context, followed by retval
+        let retval = res?
+            .first()
+            .and_then(|entry| entry.data.get(6..))
+            .and_then(|buf| i32::unpack(buf, 0).ok())
+            .map(|(retval, _)| retval);
+        match retval {
+            Some(retval) if retval != 0 => Err(Error::vpp_api(retval, None, frame.name)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Update message id & client index of frame to the current registration
+    pub fn patch_frame(&self, frame: &mut PendingFrame) -> Result<()> {
+        let reg = self.reg();
+        let msg_id = reg.lookup(frame.name, frame.crc)?;
+        let client_index = reg.client_index();
+
+        // All request messages start with `_vl_msg_id` and `client_index`
+        let offset = MessageHeader::static_size();
+        if frame.buf.len() < offset + 6 {
+            return Err(Error::internal("Request frame too short"));
+        }
+        frame.buf[offset..offset + 2].copy_from_slice(&msg_id.to_be_bytes());
+        frame.buf[offset + 2..offset + 6].copy_from_slice(&client_index.to_be_bytes());
+
+        Ok(())
+    }
+
+    /// Update context of frame, which follows `client_index`
+    pub fn patch_ctx(&self, frame: &mut PendingFrame, ctx: u32) -> Result<()> {
+        let offset = MessageHeader::static_size() + 6;
+        frame
+            .buf
+            .get_mut(offset..offset + 4)
+            .ok_or_else(|| Error::internal("Request frame too short"))?
+            .copy_from_slice(&ctx.to_be_bytes());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use crate::Error;
+
+/// Retry of calls failed by transient errors, see `ClientBuilder::retry_policy`
+///
+/// The client policy applies to requests marked `Request::IDEMPOTENT` and to dumps collected
+/// by `Client::dump_collect`, a policy in `CallOptions` applies to any request of that call.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts including the first one, 1 disables retry
+    pub max_attempts: usize,
+    /// Delay before the first retry in milliseconds, doubled after each retry
+    pub backoff: u64,
+    /// Upper bound of delay in milliseconds
+    pub max_backoff: u64,
+    /// Whether a failed attempt is retried, `RetryPolicy::transient` by default
+    pub retry_if: fn(&Error) -> bool,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(1)
+    }
+
+    pub fn backoff(mut self, ms: u64, max_ms: u64) -> Self {
+        self.backoff = ms;
+        self.max_backoff = max_ms;
+        self
+    }
+
+    /// Retry errors matched by `f`, e.g. `VppApi` with a specific `errno`
+    pub fn retry_if(mut self, f: fn(&Error) -> bool) -> Self {
+        self.retry_if = f;
+        self
+    }
+
+    /// Timeouts and lost transports
+    pub fn transient(e: &Error) -> bool {
+        matches!(e, Error::Timeout(_) | Error::Disconnected(_))
+    }
+
+    /// Delay before the retry following failed attempt `attempt`, counted from 1
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let shift = (attempt.max(1) - 1).min(16) as u32;
+        Duration::from_millis(
+            self.backoff
+                .saturating_mul(1 << shift)
+                .min(self.max_backoff),
+        )
+    }
+
+    pub(crate) fn should_retry(&self, attempt: usize, e: &Error) -> bool {
+        attempt < self.max_attempts && (self.retry_if)(e)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: 100,
+            max_backoff: 2000,
+            retry_if: Self::transient,
+        }
+    }
+}
+
+impl PartialEq for RetryPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_attempts == other.max_attempts
+            && self.backoff == other.backoff
+            && self.max_backoff == other.max_backoff
+            && std::ptr::fn_addr_eq(self.retry_if, other.retry_if)
+    }
+}
+
+impl Eq for RetryPolicy {}
@@ -0,0 +1,71 @@
+#[cfg(not(any(
+    feature = "runtime-tokio02",
+    feature = "runtime-tokio",
+    feature = "runtime-async-std",
+    feature = "runtime-smol"
+)))]
+compile_error!(
+    "One runtime feature must be enabled: runtime-tokio02, runtime-tokio, runtime-async-std or runtime-smol"
+);
+
+#[cfg(any(
+    all(feature = "runtime-tokio02", feature = "runtime-tokio"),
+    all(feature = "runtime-tokio02", feature = "runtime-async-std"),
+    all(feature = "runtime-tokio02", feature = "runtime-smol"),
+    all(feature = "runtime-tokio", feature = "runtime-async-std"),
+    all(feature = "runtime-tokio", feature = "runtime-smol"),
+    all(feature = "runtime-async-std", feature = "runtime-smol"),
+))]
+compile_error!("Only one runtime feature can be enabled, disable default features of rsvpp");
+
+#[cfg(feature = "runtime-async-std")]
+mod async_std;
+#[cfg(feature = "runtime-smol")]
+mod smol;
+#[cfg(feature = "runtime-tokio02")]
+mod tokio02;
+#[cfg(feature = "runtime-tokio")]
+mod tokio1;
+
+#[cfg(feature = "runtime-async-std")]
+pub(crate) use self::async_std::*;
+#[cfg(feature = "runtime-smol")]
+pub(crate) use self::smol::*;
+#[cfg(feature = "runtime-tokio02")]
+pub(crate) use self::tokio02::*;
+#[cfg(feature = "runtime-tokio")]
+pub(crate) use self::tokio1::*;
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+#[cfg(not(feature = "runtime-tokio02"))]
+use std::{io, os::unix::io::AsFd};
+
+use futures::future::{self, Either};
+
+/// Run future until it completes or timeout elapsed, None if timeout
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+    // Check the future first, completed result wins over timeout
+    match future::select(Box::pin(fut), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// Run future until it completes or deadline passed, None if deadline passed
+pub(crate) async fn timeout_at<F: Future>(deadline: Instant, fut: F) -> Option<F::Output> {
+    timeout(deadline.saturating_duration_since(Instant::now()), fut).await
+}
+
+/// Enable keepalive probes after the connection idled for `keepalive`
+#[cfg(not(feature = "runtime-tokio02"))]
+fn set_tcp_keepalive<S: AsFd>(stream: &S, keepalive: Option<Duration>) -> io::Result<()> {
+    let sock = socket2::SockRef::from(stream);
+    match keepalive {
+        Some(time) => sock.set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(time)),
+        None => sock.set_keepalive(false),
+    }
+}
@@ -0,0 +1,103 @@
+use std::{convert::TryFrom, future::Future, io, time::Duration};
+
+use ::smol::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{unix::UnixStream, TcpStream},
+    Timer,
+};
+
+use crate::Result;
+
+/// Spawn task on the global executor
+pub(crate) fn spawn<F>(fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    ::smol::spawn(fut).detach();
+}
+
+/// Spawn task on the global executor, never fails
+pub(crate) fn try_spawn<F>(fut: F) -> Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    spawn(fut);
+
+    Ok(())
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    Timer::after(duration).await;
+}
+
+/// Run future on the current thread, tasks are spawned on the global executor
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn block_on<F: Future>(fut: F) -> F::Output {
+    ::smol::block_on(fut)
+}
+
+pub(crate) async fn connect_unix(sock: &str) -> io::Result<(StreamReader, StreamWriter)> {
+    let stream = UnixStream::connect(sock).await?;
+
+    Ok((
+        StreamReader(Box::new(stream.clone())),
+        StreamWriter(Box::new(stream)),
+    ))
+}
+
+/// Register connected std stream to runtime, must be called inside runtime
+pub(crate) fn unix_from_std(
+    stream: std::os::unix::net::UnixStream,
+) -> io::Result<(StreamReader, StreamWriter)> {
+    let stream = UnixStream::try_from(stream)?;
+
+    Ok((
+        StreamReader(Box::new(stream.clone())),
+        StreamWriter(Box::new(stream)),
+    ))
+}
+
+pub(crate) async fn connect_tcp(
+    addr: &str,
+    keepalive: Option<Duration>,
+) -> io::Result<(StreamReader, StreamWriter)> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    super::set_tcp_keepalive(&stream, keepalive)?;
+
+    Ok((
+        StreamReader(Box::new(stream.clone())),
+        StreamWriter(Box::new(stream)),
+    ))
+}
+
+pub(crate) struct StreamReader(Box<dyn AsyncRead + Send + Unpin>);
+
+impl StreamReader {
+    pub async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf).await
+    }
+}
+
+pub(crate) struct StreamWriter(Box<dyn AsyncWrite + Send + Unpin>);
+
+impl StreamWriter {
+    pub async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf).await
+    }
+}
+
+/// Blocking client runs on the global executor, nothing is owned
+#[cfg(feature = "blocking")]
+pub(crate) struct Runtime;
+
+#[cfg(feature = "blocking")]
+impl Runtime {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        ::smol::block_on(fut)
+    }
+}
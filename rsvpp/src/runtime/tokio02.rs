@@ -0,0 +1,110 @@
+use std::{future::Future, io, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, UnixStream},
+    runtime::Handle,
+};
+
+use crate::{Error, Result};
+
+/// Spawn task on current runtime, panics outside of runtime
+pub(crate) fn spawn<F>(fut: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(fut);
+}
+
+/// Spawn task on current runtime if any
+pub(crate) fn try_spawn<F>(fut: F) -> Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let handle =
+        Handle::try_current().map_err(|e| Error::internal(format!("Runtime not found: {}", e)))?;
+    handle.spawn(fut);
+
+    Ok(())
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::delay_for(duration).await
+}
+
+/// Run future on a new current thread runtime, panics if the runtime can't be created
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn block_on<F: Future>(fut: F) -> F::Output {
+    tokio::runtime::Builder::new()
+        .basic_scheduler()
+        .enable_all()
+        .build()
+        .expect("Create runtime")
+        .block_on(fut)
+}
+
+pub(crate) async fn connect_unix(sock: &str) -> io::Result<(StreamReader, StreamWriter)> {
+    let (rd, wr) = UnixStream::connect(sock).await?.into_split();
+
+    Ok((StreamReader(Box::new(rd)), StreamWriter(Box::new(wr))))
+}
+
+/// Register connected std stream to runtime, must be called inside runtime
+pub(crate) fn unix_from_std(
+    stream: std::os::unix::net::UnixStream,
+) -> io::Result<(StreamReader, StreamWriter)> {
+    stream.set_nonblocking(true)?;
+    let (rd, wr) = UnixStream::from_std(stream)?.into_split();
+
+    Ok((StreamReader(Box::new(rd)), StreamWriter(Box::new(wr))))
+}
+
+pub(crate) async fn connect_tcp(
+    addr: &str,
+    keepalive: Option<Duration>,
+) -> io::Result<(StreamReader, StreamWriter)> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    stream.set_keepalive(keepalive)?;
+    let (rd, wr) = stream.into_split();
+
+    Ok((StreamReader(Box::new(rd)), StreamWriter(Box::new(wr))))
+}
+
+pub(crate) struct StreamReader(Box<dyn AsyncRead + Send + Unpin>);
+
+impl StreamReader {
+    pub async fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.0.read_exact(buf).await.map(|_| ())
+    }
+}
+
+pub(crate) struct StreamWriter(Box<dyn AsyncWrite + Send + Unpin>);
+
+impl StreamWriter {
+    pub async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf).await
+    }
+}
+
+/// Private runtime of blocking client, one worker thread keeps receiving between calls
+#[cfg(feature = "blocking")]
+pub(crate) struct Runtime(tokio::runtime::Runtime);
+
+#[cfg(feature = "blocking")]
+impl Runtime {
+    pub fn new() -> io::Result<Self> {
+        let rt = tokio::runtime::Builder::new()
+            .threaded_scheduler()
+            .core_threads(1)
+            .thread_name("rsvpp-blocking")
+            .enable_all()
+            .build()?;
+
+        Ok(Self(rt))
+    }
+
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.0.handle().block_on(fut)
+    }
+}
@@ -1,71 +1,596 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
 
-use tokio::{
-    sync::{broadcast, mpsc, Mutex},
-    time,
+use futures::{
+    channel::{mpsc, oneshot},
+    future::{self, Either},
+    lock::Mutex,
 };
 
 use crate::{
-    hard_coded_message::ApiMessageReplyHeader,
+    hard_coded_message::{
+        ApiMessageReplyHeader, VlApiMemclntKeepaliveReplyT, VlApiMemclntKeepaliveT,
+    },
     message::{Message, MessageHeader},
+    metrics::SessionMetrics,
     pack::Pack,
-    Error, Result, Transport,
+    runtime,
+    signal::Signal,
+    Error, Result, Transport, WireTrace,
 };
 
-type RecvCacheT = Arc<Mutex<HashMap<u32, Vec<RecvEntry>>>>;
+type StateT = Arc<Mutex<SessionState>>;
 
 const GC_LENGTH_THRESHOLD: usize = 64;
 const GC_TIME_THRESHOLD: u32 = 30; // Seconds
 
-#[derive(Debug)]
+/// Limits of replies and requests tracked by session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Gc starts when number of tracked contexts reaches it
+    pub gc_length_threshold: usize,
+    /// Seconds before unclaimed replies and abandoned requests expire
+    pub gc_time_threshold: u32,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            gc_length_threshold: GC_LENGTH_THRESHOLD,
+            gc_time_threshold: GC_TIME_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct RecvEntry {
     pub header: ApiMessageReplyHeader,
     pub data: Vec<u8>,
     pub timestamp: u32,
 }
 
+/// Request frame written to the transport, kept for resending after reconnect
+#[derive(Debug, Clone)]
+pub struct PendingFrame {
+    pub name: &'static str,
+    pub crc: &'static str,
+    pub buf: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct PendingEntry {
+    frames: Vec<PendingFrame>,
+    replied: bool,
+    timestamp: u32,
+}
+
+struct Subscriber {
+    name: &'static str,
+    crc: &'static str,
+    tx: mpsc::UnboundedSender<RecvEntry>,
+}
+
+struct SessionState {
+    config: CacheConfig,
+    connected: bool,
+    closed: bool,
+    start: Instant,
+    last_gc: u32,
+    cache: HashMap<u32, Vec<RecvEntry>>,
+    pending: HashMap<u32, PendingEntry>,
+    lost: HashMap<u32, PendingEntry>,
+    subscribers: HashMap<u16, Vec<Subscriber>>,
+    discard: HashMap<u32, (u16, u32)>,
+    keepalive: Option<(u16, u16)>,
+    gc_runs: u64,
+    gc_evictions: u64,
+    #[cfg(feature = "metrics")]
+    client: String,
+}
+
+impl SessionState {
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn new(config: CacheConfig, client: &str) -> Self {
+        Self {
+            config,
+            connected: true,
+            closed: false,
+            start: Instant::now(),
+            last_gc: 0,
+            cache: HashMap::new(),
+            pending: HashMap::new(),
+            lost: HashMap::new(),
+            subscribers: HashMap::new(),
+            discard: HashMap::new(),
+            keepalive: None,
+            gc_runs: 0,
+            gc_evictions: 0,
+            #[cfg(feature = "metrics")]
+            client: client.to_string(),
+        }
+    }
+
+    fn now(&self) -> u32 {
+        self.start.elapsed().as_secs() as u32
+    }
+
+    fn disconnect(&mut self) {
+        log::debug!("Transport lost, {} pending requests", self.pending.len());
+
+        // Requests in pending will never receive reply from current transport
+        self.connected = false;
+        let pending = std::mem::take(&mut self.pending);
+        self.lost.extend(pending);
+    }
+
+    fn release(&mut self, ctx: u32) {
+        self.pending.remove(&ctx);
+        self.lost.remove(&ctx);
+    }
+
+    fn discard(&mut self, ctx: u32, until_msg_id: u16) {
+        self.release(ctx);
+
+        // Terminator may be received already
+        if let Some(entries) = self.cache.remove(&ctx) {
+            if entries.iter().any(|e| e.header._vl_msg_id == until_msg_id) {
+                return;
+            }
+        }
+
+        let timestamp = self.now();
+        self.discard.insert(ctx, (until_msg_id, timestamp));
+    }
+
+    fn try_gc(&mut self) {
+        let now = self.now();
+        let length = self.cache.len() + self.pending.len() + self.lost.len() + self.discard.len();
+        let CacheConfig {
+            gc_length_threshold,
+            gc_time_threshold,
+        } = self.config;
+        if length < gc_length_threshold || now == self.last_gc {
+            return;
+        }
+
+        log::debug!("Start gc");
+        self.last_gc = now;
+        self.gc_runs += 1;
+        let before = self.tracked_len();
+
+        // Cleanup cache
+        let old_map = std::mem::replace(&mut self.cache, HashMap::new());
+        self.cache = old_map.into_iter().fold(HashMap::new(), |mut map, (k, v)| {
+            let new_vec = v
+                .into_iter()
+                .filter(|entry| {
+                    if now - entry.timestamp >= gc_time_threshold {
+                        log::debug!("Message {:?} expired", entry.header);
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect::<Vec<RecvEntry>>();
+
+            if new_vec.len() > 0 {
+                map.insert(k, new_vec);
+            }
+            map
+        });
+
+        // Cleanup requests
+        self.pending
+            .retain(|_, entry| now - entry.timestamp < gc_time_threshold);
+        self.lost
+            .retain(|_, entry| now - entry.timestamp < gc_time_threshold);
+        self.discard
+            .retain(|_, (_, timestamp)| now - *timestamp < gc_time_threshold);
+
+        let evictions = (before - self.tracked_len()) as u64;
+        self.gc_evictions += evictions;
+        #[cfg(feature = "metrics")]
+        ::metrics::counter!("rsvpp_gc_evictions_total", "client" => self.client.clone())
+            .increment(evictions);
+    }
+
+    /// Cached messages and tracked requests
+    fn tracked_len(&self) -> usize {
+        self.cache_messages() + self.pending.len() + self.lost.len() + self.discard.len()
+    }
+
+    fn cache_messages(&self) -> usize {
+        self.cache.values().map(Vec::len).sum()
+    }
+
+    fn metrics(&self) -> SessionMetrics {
+        SessionMetrics {
+            outstanding_contexts: self.pending.len(),
+            cache_contexts: self.cache.len(),
+            cache_messages: self.cache_messages(),
+            gc_runs: self.gc_runs,
+            gc_evictions: self.gc_evictions,
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn report_gauges(&self) {
+        let metrics = self.metrics();
+        ::metrics::gauge!("rsvpp_outstanding_contexts", "client" => self.client.clone())
+            .set(metrics.outstanding_contexts as f64);
+        ::metrics::gauge!("rsvpp_cache_messages", "client" => self.client.clone())
+            .set(metrics.cache_messages as f64);
+    }
+}
+
 pub struct Session {
-    transport: Arc<dyn Transport>,
-    recv_cache: RecvCacheT,
-    signal_tx: broadcast::Sender<()>,
-    recv_task_quit_tx: mpsc::Sender<()>,
+    transport: RwLock<Arc<dyn Transport>>,
+    state: StateT,
+    epoch: Arc<AtomicU64>,
+    signal: Signal,
+    recv_task_quit_tx: std::sync::Mutex<Option<oneshot::Sender<()>>>,
+    wire_trace: Option<Arc<WireTrace>>,
 }
 
 impl Session {
     pub fn new(transport: Arc<dyn Transport>) -> Self {
-        let (signal_tx, _) = broadcast::channel::<()>(16);
-        let (quit_tx, quit_rx) = mpsc::channel::<()>(1);
-        let recv_cache = Arc::new(Mutex::new(HashMap::new()));
+        Self::with_cache_config(transport, CacheConfig::default())
+    }
+
+    pub fn with_cache_config(transport: Arc<dyn Transport>, config: CacheConfig) -> Self {
+        Self::with_options(transport, config, None, crate::CLIENT_NAME)
+    }
+
+    /// Client name labels metrics exported with feature `metrics`
+    pub(crate) fn with_options(
+        transport: Arc<dyn Transport>,
+        config: CacheConfig,
+        wire_trace: Option<Arc<WireTrace>>,
+        client: &str,
+    ) -> Self {
+        let signal = Signal::new();
+        let state = Arc::new(Mutex::new(SessionState::new(config, client)));
+        let epoch = Arc::new(AtomicU64::new(0));
 
         // Create recv task
-        RecvTask::start(
-            recv_cache.clone(),
+        let quit_tx = RecvTask::start(
+            state.clone(),
             transport.clone(),
-            signal_tx.clone(),
-            quit_rx,
+            epoch.clone(),
+            signal.clone(),
+            wire_trace.clone(),
         );
 
         Self {
-            transport,
-            recv_cache,
-            signal_tx,
-            recv_task_quit_tx: quit_tx,
+            transport: RwLock::new(transport),
+            state,
+            epoch,
+            signal,
+            recv_task_quit_tx: std::sync::Mutex::new(Some(quit_tx)),
+            wire_trace,
         }
     }
 
+    pub(crate) fn wire_trace(&self) -> Option<&Arc<WireTrace>> {
+        self.wire_trace.as_ref()
+    }
+
+    /// Number of transport losses since the session was created
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    pub async fn is_connected(&self) -> bool {
+        self.state.lock().await.connected
+    }
+
+    pub async fn metrics(&self) -> SessionMetrics {
+        self.state.lock().await.metrics()
+    }
+
+    pub async fn set_transport(&self, transport: Arc<dyn Transport>) {
+        log::debug!("Replace session transport");
+
+        // Stop old recv task
+        self.stop_recv_task();
+
+        // Replace transport
+        *self.transport.write().unwrap_or_else(|e| e.into_inner()) = transport.clone();
+
+        // Start new recv task
+        let quit_tx = RecvTask::start(
+            self.state.clone(),
+            transport,
+            self.epoch.clone(),
+            self.signal.clone(),
+            self.wire_trace.clone(),
+        );
+        *self
+            .recv_task_quit_tx
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(quit_tx);
+
+        // XXX: sockclnt reply context is 0, drop stale messages
+        let mut state = self.state.lock().await;
+        state.cache.remove(&0);
+        state.connected = true;
+    }
+
     pub async fn send_msg<T: Pack>(&self, mut msg: Message<T>, timeout: u64) -> Result<()> {
         let buf = msg.encode()?;
 
-        tokio::select! {
-            _ = time::delay_for(time::Duration::from_millis(timeout)) => Err(Error::timeout("Send timeout")),
-            res = self.transport.write(&buf) => res,
+        self.write(&buf, timeout).await
+    }
+
+    /// Send message in background without waiting, used where awaiting is impossible
+    pub fn send_msg_detached<T: Pack>(&self, mut msg: Message<T>) -> Result<()> {
+        let buf = msg.encode()?;
+        let transport = self.transport();
+        let wire_trace = self.wire_trace.clone();
+
+        runtime::try_spawn(async move {
+            if let Some(trace) = wire_trace {
+                trace.send(&buf);
+            }
+            if let Err(e) = transport.write(&buf).await {
+                log::debug!("Detached send error: {}", e);
+            }
+        })
+    }
+
+    /// Send message and track it until reply received
+    pub async fn send_request<T: Pack>(
+        &self,
+        ctx: u32,
+        name: &'static str,
+        crc: &'static str,
+        mut msg: Message<T>,
+        timeout: u64,
+    ) -> Result<()> {
+        let buf = msg.encode()?;
+
+        self.send_requests(vec![(ctx, PendingFrame { name, crc, buf })], timeout)
+            .await
+    }
+
+    /// Send frames in a single write and track them until reply received
+    pub async fn send_requests(
+        &self,
+        frames: Vec<(u32, PendingFrame)>,
+        timeout: u64,
+    ) -> Result<()> {
+        let mut buf: Vec<u8> = Vec::with_capacity(frames.iter().map(|(_, f)| f.buf.len()).sum());
+        let mut ctxs: Vec<u32> = Vec::with_capacity(frames.len());
+
+        // Add to pending
+        {
+            let mut state = self.state.lock().await;
+            if state.closed {
+                return Err(Error::closed("Session closed"));
+            }
+            if !state.connected {
+                return Err(Error::disconnected("Transport lost"));
+            }
+
+            let timestamp = state.now();
+            for (ctx, frame) in frames {
+                buf.extend_from_slice(&frame.buf);
+                ctxs.push(ctx);
+
+                let entry = state.pending.entry(ctx).or_insert_with(|| PendingEntry {
+                    frames: Vec::new(),
+                    replied: false,
+                    timestamp,
+                });
+                if !entry.replied {
+                    entry.frames.push(frame);
+                }
+            }
+        }
+
+        // Caller won't wait for reply if send failed
+        let res = self.write(&buf, timeout).await;
+        if res.is_err() {
+            let mut state = self.state.lock().await;
+            for ctx in ctxs {
+                state.pending.remove(&ctx);
+            }
+        }
+
+        res
+    }
+
+    /// Resend lost requests which have not received any reply
+    pub async fn retry_lost<F>(&self, mut patch: F, timeout: u64) -> Result<()>
+    where
+        F: FnMut(&mut PendingFrame) -> Result<()>,
+    {
+        let mut bufs: Vec<Vec<u8>> = Vec::new();
+        {
+            let mut state = self.state.lock().await;
+            let now = state.now();
+            let ctxs = state
+                .lost
+                .iter()
+                .filter(|(_, entry)| !entry.replied)
+                .map(|(ctx, _)| *ctx)
+                .collect::<Vec<_>>();
+
+            for ctx in ctxs {
+                let mut entry = match state.lost.remove(&ctx) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+
+                // Update message id & client index, keep it lost if failed
+                if let Err(e) = entry.frames.iter_mut().try_for_each(&mut patch) {
+                    log::warn!("Unable to retry ctx {}: {}", ctx, e);
+                    entry.replied = true;
+                    state.lost.insert(ctx, entry);
+                    continue;
+                }
+
+                log::debug!("Retry ctx {}", ctx);
+                bufs.extend(entry.frames.iter().map(|f| f.buf.clone()));
+                entry.timestamp = now;
+                state.pending.insert(ctx, entry);
+            }
+        }
+
+        for buf in bufs {
+            self.write(&buf, timeout).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait until all pending requests received reply
+    pub async fn drain(&self, timeout: u64) -> Result<()> {
+        runtime::timeout(Duration::from_millis(timeout), self.internal_drain())
+            .await
+            .unwrap_or_else(|| Err(Error::timeout("Drain timeout")))
+    }
+
+    async fn internal_drain(&self) -> Result<()> {
+        let mut signal_rx = self.signal.subscribe();
+        loop {
+            {
+                let state = self.state.lock().await;
+                let count = state.pending.values().filter(|e| !e.replied).count();
+                if count == 0 || !state.connected || state.closed {
+                    return Ok(());
+                }
+                log::trace!("Wait {} pending requests", count);
+            }
+
+            signal_rx.recv().await;
+        }
+    }
+
+    /// Answer `memclnt_keepalive` of msg_id with `memclnt_keepalive_reply` of reply_id
+    pub async fn set_keepalive(&self, msg_id: u16, reply_id: u16) {
+        self.state.lock().await.keepalive = Some((msg_id, reply_id));
+    }
+
+    /// Route messages with msg_id to returned receiver instead of cache
+    pub async fn subscribe(
+        &self,
+        msg_id: u16,
+        name: &'static str,
+        crc: &'static str,
+    ) -> mpsc::UnboundedReceiver<RecvEntry> {
+        let (tx, rx) = mpsc::unbounded();
+        self.state
+            .lock()
+            .await
+            .subscribers
+            .entry(msg_id)
+            .or_insert_with(Vec::new)
+            .push(Subscriber { name, crc, tx });
+
+        rx
+    }
+
+    /// Update subscribed message ids after message table changed
+    pub async fn remap_subscribers<F>(&self, mut lookup: F)
+    where
+        F: FnMut(&str, &str) -> Result<u16>,
+    {
+        let mut state = self.state.lock().await;
+        let old_map = std::mem::take(&mut state.subscribers);
+        for sub in old_map.into_values().flatten() {
+            match lookup(sub.name, sub.crc) {
+                Ok(msg_id) => state
+                    .subscribers
+                    .entry(msg_id)
+                    .or_insert_with(Vec::new)
+                    .push(sub),
+                Err(e) => log::warn!("Drop subscriber of '{}': {}", sub.name, e),
+            }
+        }
+    }
+
+    /// Stop receiving, waiters without cached reply fail with `Error::Closed`
+    pub async fn close(&self) {
+        self.stop_recv_task();
+
+        let mut state = self.state.lock().await;
+        state.closed = true;
+        state.pending.clear();
+        state.lost.clear();
+        state.subscribers.clear();
+        drop(state);
+
+        self.signal.send();
+    }
+
+    /// Drop replies of ctx until message `until_msg_id` received
+    pub fn discard(&self, ctx: u32, until_msg_id: u16) {
+        log::trace!("Discard ctx {} until {}", ctx, until_msg_id);
+
+        // Called from drop, lock state in background if possible
+        let state = self.state.clone();
+        let res = runtime::try_spawn(async move {
+            state.lock().await.discard(ctx, until_msg_id);
+        });
+        if res.is_err() {
+            match self.state.try_lock() {
+                Some(mut state) => state.discard(ctx, until_msg_id),
+                None => log::warn!("Unable to discard ctx {}", ctx),
+            }
+        }
+    }
+
+    /// Whether ctx is waiting for reply, has unclaimed replies or is being discarded
+    pub async fn is_outstanding(&self, ctx: u32) -> bool {
+        let state = self.state.lock().await;
+
+        state.pending.contains_key(&ctx)
+            || state.lost.contains_key(&ctx)
+            || state.cache.contains_key(&ctx)
+            || state.discard.contains_key(&ctx)
+    }
+
+    /// Whether request of ctx is lost and will not be retried
+    pub async fn is_lost(&self, ctx: u32) -> bool {
+        self.state.lock().await.lost.contains_key(&ctx)
+    }
+
+    /// Stop tracking request of ctx
+    pub async fn release(&self, ctx: u32) {
+        self.state.lock().await.release(ctx);
+    }
+
+    /// Like `release`, for callers which can't wait, e.g. `Dump` in `poll_next` and drop
+    pub fn release_now(&self, ctx: u32) {
+        if let Some(mut state) = self.state.try_lock() {
+            state.release(ctx);
+            return;
+        }
+
+        let state = self.state.clone();
+        let res = runtime::try_spawn(async move {
+            state.lock().await.release(ctx);
+        });
+        if res.is_err() {
+            log::warn!("Unable to release ctx {}", ctx);
         }
     }
 
     pub async fn recv_single_msg<T: Pack>(&self, ctx: u32, msg_id: u16, timeout: u64) -> Result<T> {
         // Recv data
-        let mut entries = self.recv(ctx, timeout).await?;
+        let entries = self.recv(ctx, timeout).await?;
 
+        Self::decode_single_msg(entries, msg_id)
+    }
+
+    pub fn decode_single_msg<T: Pack>(mut entries: Vec<RecvEntry>, msg_id: u16) -> Result<T> {
         // Verify entries length
         if entries.len() != 1 {
             return Err(Error::internal("Message length not 1"));
@@ -88,87 +613,154 @@ impl Session {
     }
 
     pub async fn recv(&self, ctx: u32, timeout: u64) -> Result<Vec<RecvEntry>> {
-        tokio::select! {
-            _ = time::delay_for(time::Duration::from_millis(timeout)) => Err(Error::timeout("Recv timeout")),
-            res = self.internal_recv(ctx) => res,
-        }
+        runtime::timeout(Duration::from_millis(timeout), self.internal_recv(ctx))
+            .await
+            .unwrap_or_else(|| Err(Error::timeout("Recv timeout")))
     }
 
     async fn internal_recv(&self, ctx: u32) -> Result<Vec<RecvEntry>> {
         log::trace!("Recv msg from ctx {}", ctx);
-        let mut signal_rx = self.signal_tx.subscribe();
+        let mut signal_rx = self.signal.subscribe();
         let entries = loop {
-            // Get message from cache
-            if let Some(entries) = self.recv_cache.lock().await.remove(&ctx) {
-                break entries;
+            {
+                let mut state = self.state.lock().await;
+
+                // Get message from cache
+                if let Some(entries) = state.cache.remove(&ctx) {
+                    break entries;
+                }
+
+                if state.closed {
+                    return Err(Error::closed(format!(
+                        "Session closed while waiting ctx {}",
+                        ctx
+                    )));
+                }
+
+                // Reply will never come
+                if !state.connected || state.lost.contains_key(&ctx) {
+                    return Err(Error::disconnected(format!(
+                        "Transport lost while waiting ctx {}",
+                        ctx
+                    )));
+                }
             }
 
             // Wait signal
             log::trace!("Wait signal, ctx: {}", ctx);
-            signal_rx
-                .recv()
-                .await
-                .map_err(|e| Error::internal(format!("Recv signal error: {}", e)))?;
+            signal_rx.recv().await;
         };
 
         Ok(entries)
     }
+
+    async fn write(&self, buf: &[u8], timeout: u64) -> Result<()> {
+        let transport = self.transport();
+
+        // Trace before write, the reply may be received before write returns
+        if let Some(trace) = &self.wire_trace {
+            trace.send(buf);
+        }
+
+        runtime::timeout(Duration::from_millis(timeout), transport.write(buf))
+            .await
+            .unwrap_or_else(|| Err(Error::timeout("Send timeout")))
+    }
+
+    fn transport(&self) -> Arc<dyn Transport> {
+        self.transport
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn stop_recv_task(&self) {
+        log::debug!("Send quit signal to RecvTask");
+        self.recv_task_quit_tx
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .map(|tx| tx.send(()));
+    }
 }
 
 impl Drop for Session {
     fn drop(&mut self) {
-        log::debug!("Send quit signal to RecvTask");
-        self.recv_task_quit_tx.try_send(()).ok();
+        self.stop_recv_task();
     }
 }
 
 struct RecvTask {
-    cache: RecvCacheT,
+    state: StateT,
     transport: Arc<dyn Transport>,
-    signal_tx: broadcast::Sender<()>,
+    epoch: Arc<AtomicU64>,
+    signal: Signal,
+    wire_trace: Option<Arc<WireTrace>>,
 }
 
 impl RecvTask {
     pub fn start(
-        cache: RecvCacheT,
+        state: StateT,
         transport: Arc<dyn Transport>,
-        signal_tx: broadcast::Sender<()>,
-        quit_rx: mpsc::Receiver<()>,
-    ) {
+        epoch: Arc<AtomicU64>,
+        signal: Signal,
+        wire_trace: Option<Arc<WireTrace>>,
+    ) -> oneshot::Sender<()> {
+        let (quit_tx, quit_rx) = oneshot::channel::<()>();
         let mut instance = Self {
-            cache,
+            state,
             transport,
-            signal_tx,
+            epoch,
+            signal,
+            wire_trace,
         };
 
-        tokio::spawn(async move {
+        runtime::spawn(async move {
             instance.run(quit_rx).await;
         });
+
+        quit_tx
     }
 
-    async fn run(&mut self, mut quit_rx: mpsc::Receiver<()>) {
+    async fn run(&mut self, mut quit_rx: oneshot::Receiver<()>) {
         loop {
-            tokio::select! {
-                _ = quit_rx.recv() => {
+            // Quit signal or sender dropped
+            let res = match future::select(&mut quit_rx, Box::pin(self.recv_frame())).await {
+                Either::Left(_) => {
                     log::debug!("Quit RecvTask");
                     break;
                 }
-                res = self.recv_frame() => {
-                    if let Err(e) = res {
-                        log::warn!("Recv frame error {}", e);
-                        tokio::time::delay_for(tokio::time::Duration::from_secs(3)).await;
-                    } else {
-                        // Send signal
-                        log::trace!("Send signal, rx count: {}", self.signal_tx.receiver_count());
-                        if let Err(_) = self.signal_tx.send(()) {
-                            // Ignore
-                        }
-                    }
-                }
+                Either::Right((res, _)) => res,
             };
+
+            match res {
+                Err(Error::Io(e)) => {
+                    log::warn!("Recv frame error {}, transport lost", e);
+                    self.disconnect().await;
+                    break;
+                }
+                Err(e) => log::warn!("Recv frame error {}", e),
+                Ok(_) => self.notify(),
+            }
         }
     }
 
+    fn notify(&self) {
+        // Send signal
+        log::trace!("Send signal");
+        self.signal.send();
+    }
+
+    async fn disconnect(&self) {
+        // Update epoch before waking up waiters
+        let mut state = self.state.lock().await;
+        state.disconnect();
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+        drop(state);
+
+        self.notify();
+    }
+
     async fn recv_frame(&mut self) -> Result<()> {
         // Receive header
         log::trace!("Try recv header");
@@ -183,60 +775,93 @@ impl RecvTask {
         log::trace!("Try recv data");
         let data_buf = self.transport.read(header.len as usize).await?;
         log::trace!("Data length is: {:?}", data_buf.len());
+        if let Some(trace) = &self.wire_trace {
+            trace.recv(&header_buf, &data_buf);
+        }
 
         // Decode message header
         let msg_header = ApiMessageReplyHeader::unpack(&data_buf, 0)?.0;
         log::trace!("Data header is: {:?}", msg_header);
 
-        // Lock cache & try to gc
-        let mut cache = self.cache.lock().await;
-        if cache.len() >= GC_LENGTH_THRESHOLD {
-            Self::gc(&mut cache);
+        // Lock state & try to gc
+        let mut state = self.state.lock().await;
+        state.try_gc();
+        #[cfg(feature = "metrics")]
+        state.report_gauges();
+
+        // Keepalive from vpp, client is removed if not answered
+        if let Some((msg_id, reply_id)) = state.keepalive {
+            if msg_header._vl_msg_id == msg_id {
+                drop(state);
+                return self.reply_keepalive(&data_buf, reply_id).await;
+            }
         }
 
-        // Add message to cache
+        // Events go to subscribers by message id, the context slot of events is `client_index`
+        let msg_id = msg_header._vl_msg_id;
+        let timestamp = state.now();
+        if let Some(subs) = state.subscribers.get_mut(&msg_id) {
+            log::trace!("Dispatch to {} subscribers", subs.len());
+            let entry = RecvEntry {
+                header: msg_header,
+                data: data_buf,
+                timestamp,
+            };
+            subs.retain(|sub| sub.tx.unbounded_send(entry.clone()).is_ok());
+            if subs.is_empty() {
+                state.subscribers.remove(&msg_id);
+            }
+            return Ok(());
+        }
+
+        // Reply of abandoned request
         let ctx = msg_header.context;
+        if let Some((until_msg_id, _)) = state.discard.get(&ctx) {
+            log::trace!("Discard message {:?}", msg_header);
+            if msg_header._vl_msg_id == *until_msg_id {
+                state.discard.remove(&ctx);
+            }
+            return Ok(());
+        }
+
+        // Reply received, request will not be resent
+        if let Some(pending) = state.pending.get_mut(&ctx) {
+            pending.replied = true;
+            pending.frames.clear();
+        }
+
         let entry = RecvEntry {
             header: msg_header,
             data: data_buf,
-            timestamp: Instant::now().elapsed().as_secs() as u32,
+            timestamp,
         };
-        if let Some(old_vec) = cache.get_mut(&ctx) {
+
+        // Add message to cache
+        if let Some(old_vec) = state.cache.get_mut(&ctx) {
             log::trace!("Append to cache '{}'", entry.header.context);
             old_vec.push(entry);
         } else {
             log::trace!("New to cache '{}'", entry.header.context);
-            cache.insert(ctx, vec![entry]);
+            state.cache.insert(ctx, vec![entry]);
         };
 
         Ok(())
     }
 
-    fn gc(map: &mut HashMap<u32, Vec<RecvEntry>>) {
-        log::debug!("Start gc");
-
-        // Take map ownership
-        let old_map = std::mem::replace(map, HashMap::new());
-
-        // Replace map
-        *map = old_map.into_iter().fold(HashMap::new(), |mut map, (k, v)| {
-            let new_vec = v
-                .into_iter()
-                .filter(|entry| {
-                    let now = Instant::now().elapsed().as_secs() as u32;
-                    if now - entry.timestamp >= GC_TIME_THRESHOLD {
-                        log::debug!("Message {:?} expired", entry.header);
-                        false
-                    } else {
-                        true
-                    }
-                })
-                .collect::<Vec<RecvEntry>>();
+    async fn reply_keepalive(&self, buf: &[u8], reply_id: u16) -> Result<()> {
+        let keepalive = VlApiMemclntKeepaliveT::unpack(buf, 0)?.0;
+        log::trace!("Reply keepalive, ctx: {}", keepalive.context);
 
-            if new_vec.len() > 0 {
-                map.insert(k, new_vec);
-            }
-            map
+        let mut msg = Message::new(VlApiMemclntKeepaliveReplyT {
+            _vl_msg_id: reply_id,
+            context: keepalive.context,
+            retval: 0,
         });
+        let buf = msg.encode()?;
+        if let Some(trace) = &self.wire_trace {
+            trace.send(&buf);
+        }
+
+        self.transport.write(&buf).await
     }
 }
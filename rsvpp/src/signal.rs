@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Default)]
+struct SignalState {
+    generation: u64,
+    next_id: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+/// Wake up all listeners, replacement of a broadcast channel without payload
+#[derive(Clone, Default)]
+pub(crate) struct Signal {
+    state: Arc<Mutex<SignalState>>,
+}
+
+impl Signal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listener sees signals sent after it's created
+    pub fn subscribe(&self) -> Listener {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+
+        Listener {
+            signal: self.clone(),
+            id,
+            generation: state.generation,
+        }
+    }
+
+    pub fn send(&self) {
+        let wakers = {
+            let mut state = self.lock();
+            state.generation += 1;
+            std::mem::take(&mut state.wakers)
+        };
+
+        wakers.into_values().for_each(Waker::wake);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SignalState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub(crate) struct Listener {
+    signal: Signal,
+    id: u64,
+    generation: u64,
+}
+
+impl Listener {
+    /// Wait next signal, returns immediately if signaled since last wait
+    pub fn recv(&mut self) -> Recv<'_> {
+        Recv { listener: self }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.signal.lock().wakers.remove(&self.id);
+    }
+}
+
+pub(crate) struct Recv<'a> {
+    listener: &'a mut Listener,
+}
+
+impl<'a> Future for Recv<'a> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let listener = &mut *self.listener;
+        let mut state = listener.signal.lock();
+        if state.generation != listener.generation {
+            listener.generation = state.generation;
+            state.wakers.remove(&listener.id);
+            return Poll::Ready(());
+        }
+
+        state.wakers.insert(listener.id, cx.waker().clone());
+        Poll::Pending
+    }
+}
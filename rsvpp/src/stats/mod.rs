@@ -42,7 +42,7 @@ impl Stats {
         let header = sock.get_mmap_header()?;
 
         if header.version != 2 {
-            return Err(Error::vpp_api("Stats version mismatch"));
+            return Err(Error::internal("Stats version mismatch"));
         }
 
         Ok(Self { header })
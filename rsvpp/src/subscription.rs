@@ -0,0 +1,52 @@
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::{channel::mpsc, Stream, StreamExt};
+
+use crate::{pack::Pack, RecvEntry};
+
+/// Stream of messages pushed by vpp without request, e.g. `sw_interface_event`
+///
+/// Messages which fail to decode are skipped. The stream ends when client closed.
+pub struct Subscription<T> {
+    rx: mpsc::UnboundedReceiver<RecvEntry>,
+    /// Keeps the enable request sent again after reconnect, see `Client::subscribe_with`
+    _enable: Option<Arc<()>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Subscription<T> {
+    pub(crate) fn new(rx: mpsc::UnboundedReceiver<RecvEntry>) -> Self {
+        Self {
+            rx,
+            _enable: None,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn with_enable(mut self, token: Arc<()>) -> Self {
+        self._enable = Some(token);
+        self
+    }
+}
+
+impl<T: Pack> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.rx.poll_next_unpin(cx) {
+                Poll::Ready(Some(entry)) => match T::unpack(&entry.data, 0) {
+                    Ok((msg, _)) => return Poll::Ready(Some(msg)),
+                    Err(e) => log::warn!("Decode event {:?} error: {}", entry.header, e),
+                },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::{Read, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    hard_coded_message::*,
+    message::{
+        Message, MessageContext, MessageCrc, MessageDesc, MessageHeader, MessageId, MessageName,
+    },
+    pack::Pack,
+    runtime, Error, Result,
+};
+
+/// First id of the mock message table, ids below are reserved for sockclnt_create
+const MSG_ID_BASE: u16 = 20;
+
+static SOCKET_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+type Handler = Arc<dyn Fn(&ReceivedMessage, &Table) -> Result<Vec<Vec<u8>>> + Send + Sync>;
+
+/// Message received by `MockVpp`
+#[derive(Debug, Clone)]
+pub struct ReceivedMessage {
+    /// Name without crc, `unknown` if id is not in the message table
+    pub name: String,
+    pub msg_id: u16,
+    pub context: u32,
+    /// Message body including `_vl_msg_id`
+    pub payload: Vec<u8>,
+}
+
+impl ReceivedMessage {
+    pub fn decode<T: Pack>(&self) -> Result<T> {
+        Ok(T::unpack(&self.payload, 0)?.0)
+    }
+}
+
+/// In-process VPP API server on a temporary unix socket, for tests without VPP
+///
+/// Replies are scripted per message name with `on` and `on_dump`, `control_ping`,
+/// `sockclnt_delete` and `api_versions` are answered by default. Messages without
+/// handler are recorded and never replied.
+pub struct MockVpp {
+    path: PathBuf,
+    shared: Arc<Shared>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    closed: AtomicBool,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    table: Table,
+    handlers: HashMap<String, Handler>,
+    modules: Vec<(String, u32, u32, u32)>,
+    received: Vec<ReceivedMessage>,
+    conns: Vec<Arc<Mutex<UnixStream>>>,
+    next_client_index: u32,
+}
+
+#[derive(Clone, Default)]
+struct Table(Vec<(String, String)>);
+
+impl Table {
+    fn add(&mut self, name: &str, crc: &str) -> u16 {
+        let index = match self.0.iter().position(|(n, _)| n == name) {
+            Some(index) => {
+                self.0[index].1 = crc.to_string();
+                index
+            }
+            None => {
+                self.0.push((name.to_string(), crc.to_string()));
+                self.0.len() - 1
+            }
+        };
+
+        MSG_ID_BASE + index as u16
+    }
+
+    fn id<T: MessageName>(&self) -> Result<u16> {
+        let name = T::message_name();
+        self.0
+            .iter()
+            .position(|(n, _)| *n == name)
+            .map(|index| MSG_ID_BASE + index as u16)
+            .ok_or_else(|| Error::internal(format!("Message {} not in mock table", name)))
+    }
+
+    fn name(&self, id: u16) -> Option<&str> {
+        let index = id.checked_sub(MSG_ID_BASE)? as usize;
+        self.0.get(index).map(|(n, _)| n.as_str())
+    }
+
+    fn entries(&self) -> Vec<VlApiMessageTableEntryT> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(index, (name, crc))| VlApiMessageTableEntryT {
+                index: MSG_ID_BASE + index as u16,
+                name: format!("{}_{}", name, crc),
+            })
+            .collect()
+    }
+}
+
+impl MockVpp {
+    pub fn start() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "rsvpp-mock-{}-{}.sock",
+            std::process::id(),
+            SOCKET_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+
+        let shared = Arc::new(Shared {
+            closed: AtomicBool::new(false),
+            state: Mutex::new(State {
+                next_client_index: 1,
+                ..Default::default()
+            }),
+        });
+
+        let mock = Self {
+            path,
+            shared: shared.clone(),
+            accept_thread: Some(std::thread::spawn(move || accept_loop(listener, shared))),
+        };
+        mock.add_builtin_handlers();
+
+        Ok(mock)
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Socket path as expected by `Client::connect_unix`
+    pub fn socket(&self) -> &str {
+        self.path.to_str().unwrap_or_default()
+    }
+
+    /// Add message to the table sent to new clients, returns its id
+    pub fn add_message(&self, name: &str, crc: &str) -> u16 {
+        self.state().table.add(name, crc)
+    }
+
+    pub fn add_messages(&self, messages: &[MessageDesc]) {
+        let mut state = self.state();
+        for msg in messages {
+            state.table.add(msg.name, msg.crc);
+        }
+    }
+
+    /// Module returned by `api_versions`
+    pub fn add_module(&self, name: &str, major: u32, minor: u32, patch: u32) {
+        self.state()
+            .modules
+            .push((name.to_string(), major, minor, patch));
+    }
+
+    /// Reply every `Req` with the message returned by `f`, id and context are filled in
+    pub fn on<Req, Rep, F>(&self, f: F)
+    where
+        Req: Pack + MessageName + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc + MessageId + MessageContext,
+        F: Fn(Req) -> Rep + Send + Sync + 'static,
+    {
+        self.handle::<Req, Rep, _>(move |req| vec![f(req)]);
+    }
+
+    /// Reply every `Req` with the details returned by `f`, the dump is terminated
+    /// by the default `control_ping` handler
+    pub fn on_dump<Req, Rep, F>(&self, f: F)
+    where
+        Req: Pack + MessageName + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc + MessageId + MessageContext,
+        F: Fn(Req) -> Vec<Rep> + Send + Sync + 'static,
+    {
+        self.handle::<Req, Rep, _>(f);
+    }
+
+    /// Send message to all connected clients, context is left as is
+    pub fn send_event<T>(&self, msg: T) -> Result<()>
+    where
+        T: Pack + MessageName + MessageCrc + MessageId,
+    {
+        let (frame, conns) = {
+            let state = self.state();
+            let msg = msg.set_message_id(state.table.id::<T>()?);
+            (Message::new(msg).encode()?, state.conns.clone())
+        };
+
+        for conn in conns {
+            lock(&conn).write_all(&frame)?;
+        }
+
+        Ok(())
+    }
+
+    /// All messages received so far, in order
+    pub fn received(&self) -> Vec<ReceivedMessage> {
+        self.state().received.clone()
+    }
+
+    /// Received messages of type `T`, decoded
+    pub fn received_of<T: Pack + MessageName>(&self) -> Result<Vec<T>> {
+        let name = T::message_name();
+        self.state()
+            .received
+            .iter()
+            .filter(|msg| msg.name == name)
+            .map(ReceivedMessage::decode)
+            .collect()
+    }
+
+    pub fn clear_received(&self) {
+        self.state().received.clear();
+    }
+
+    /// Close connections of all clients, the mock keeps accepting new ones
+    pub fn disconnect_all(&self) {
+        for conn in self.state().conns.drain(..) {
+            let _ = lock(&conn).shutdown(Shutdown::Both);
+        }
+    }
+
+    fn handle<Req, Rep, F>(&self, f: F)
+    where
+        Req: Pack + MessageName + MessageCrc,
+        Rep: Pack + MessageName + MessageCrc + MessageId + MessageContext,
+        F: Fn(Req) -> Vec<Rep> + Send + Sync + 'static,
+    {
+        let mut state = self.state();
+        state.table.add(Req::message_name(), Req::crc());
+        state.table.add(Rep::message_name(), Rep::crc());
+
+        let handler: Handler = Arc::new(move |msg, table| {
+            let rep_id = table.id::<Rep>()?;
+            f(msg.decode()?)
+                .into_iter()
+                .map(|rep| {
+                    let rep = rep.set_message_id(rep_id).set_context(msg.context);
+                    Message::new(rep).encode()
+                })
+                .collect()
+        });
+        state
+            .handlers
+            .insert(Req::message_name().to_string(), handler);
+    }
+
+    fn add_builtin_handlers(&self) {
+        let pid = std::process::id();
+        self.on(move |req: VlApiControlPingT| VlApiControlPingReplyT {
+            _vl_msg_id: 0,
+            context: 0,
+            retval: 0,
+            client_index: req.client_index,
+            vpe_pid: pid,
+        });
+        self.on(|_: VlApiSockclntDeleteT| VlApiSockclntDeleteReplyT {
+            _vl_msg_id: 0,
+            context: 0,
+            response: 0,
+        });
+
+        // Keepalive is sent by the mock only, keep both in table so client answers it
+        self.add_message(
+            VlApiMemclntKeepaliveT::message_name(),
+            VlApiMemclntKeepaliveT::crc(),
+        );
+        self.add_message(
+            VlApiMemclntKeepaliveReplyT::message_name(),
+            VlApiMemclntKeepaliveReplyT::crc(),
+        );
+
+        // Modules are read on each request
+        let shared = Arc::downgrade(&self.shared);
+        self.on(move |_: VlApiApiVersionsT| {
+            let modules = shared
+                .upgrade()
+                .map(|shared| lock(&shared.state).modules.clone())
+                .unwrap_or_default();
+            VlApiApiVersionsReplyT {
+                _vl_msg_id: 0,
+                context: 0,
+                retval: 0,
+                count: modules.len() as u32,
+                api_versions: modules
+                    .into_iter()
+                    .map(|(name, major, minor, patch)| VlApiModuleVersionT {
+                        major,
+                        minor,
+                        patch,
+                        name,
+                    })
+                    .collect(),
+            }
+        });
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        lock(&self.shared.state)
+    }
+}
+
+impl Drop for MockVpp {
+    fn drop(&mut self) {
+        // Wake up accept loop
+        self.shared.closed.store(true, Ordering::SeqCst);
+        let _ = UnixStream::connect(&self.path);
+        if let Some(thread) = self.accept_thread.take() {
+            let _ = thread.join();
+        }
+
+        self.disconnect_all();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Run future on the runtime selected by features, so tests with `MockVpp` run on any runtime
+///
+/// With tokio a new runtime is created for each call, panics if it can't be created.
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    runtime::block_on(fut)
+}
+
+/// Sleep on the runtime selected by features
+pub async fn sleep(duration: Duration) {
+    runtime::sleep(duration).await
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn accept_loop(listener: UnixListener, shared: Arc<Shared>) {
+    for stream in listener.incoming() {
+        if shared.closed.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::error!("Mock accept error: {}", e);
+                continue;
+            }
+        };
+        let (reader, writer) = match stream.try_clone() {
+            Ok(reader) => (reader, Arc::new(Mutex::new(stream))),
+            Err(e) => {
+                log::error!("Mock clone stream error: {}", e);
+                continue;
+            }
+        };
+        lock(&shared.state).conns.push(writer.clone());
+
+        let shared = shared.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve(reader, writer, shared) {
+                log::debug!("Mock connection closed: {}", e);
+            }
+        });
+    }
+}
+
+fn serve(
+    mut reader: UnixStream,
+    writer: Arc<Mutex<UnixStream>>,
+    shared: Arc<Shared>,
+) -> Result<()> {
+    loop {
+        // Read frame
+        let mut header_buf = vec![0u8; MessageHeader::static_size()];
+        reader.read_exact(&mut header_buf)?;
+        let header = MessageHeader::decode(&header_buf)?;
+        let mut payload = vec![0u8; header.len as usize];
+        reader.read_exact(&mut payload)?;
+        let msg_id = u16::unpack(&payload, 0)?.0;
+
+        // Sockclnt create is answered with the message table
+        if msg_id == VL_API_SOCK_CLNT_CREATE_MSG_ID {
+            let req = VlApiSockclntCreateT::unpack(&payload, 0)?.0;
+            let frame = {
+                let mut state = lock(&shared.state);
+                state.received.push(ReceivedMessage {
+                    name: VlApiSockclntCreateT::message_name().to_string(),
+                    msg_id,
+                    context: req.context,
+                    payload,
+                });
+
+                let index = state.next_client_index;
+                state.next_client_index += 1;
+                let message_table = state.table.entries();
+                Message::new(VlApiSockclntCreateReplyT {
+                    _vl_msg_id: VL_API_SOCK_CLNT_CREATE_REP_MSG_ID,
+                    client_index: 0,
+                    context: 0,
+                    response: 0,
+                    index,
+                    count: message_table.len() as u16,
+                    message_table,
+                })
+                .encode()?
+            };
+            lock(&writer).write_all(&frame)?;
+            continue;
+        }
+
+        // Record and dispatch by name
+        let (msg, handler, table) = {
+            let mut state = lock(&shared.state);
+            let name = state.table.name(msg_id).unwrap_or("unknown").to_string();
+            // Replies sent by the client put context right after the id
+            let offset = if name.ends_with("_reply") { 2 } else { 6 };
+            let context = payload
+                .get(offset..)
+                .and_then(|buf| u32::unpack(buf, 0).ok())
+                .map(|(ctx, _)| ctx)
+                .unwrap_or_default();
+            let msg = ReceivedMessage {
+                name,
+                msg_id,
+                context,
+                payload,
+            };
+            state.received.push(msg.clone());
+            let handler = state.handlers.get(&msg.name).cloned();
+
+            (msg, handler, state.table.clone())
+        };
+
+        let handler = match handler {
+            Some(handler) => handler,
+            None => {
+                log::debug!("Mock has no handler for {}", msg.name);
+                continue;
+            }
+        };
+        match handler(&msg, &table) {
+            Ok(frames) => {
+                for frame in frames {
+                    lock(&writer).write_all(&frame)?;
+                }
+            }
+            Err(e) => log::error!("Mock handler of {} failed: {}", msg.name, e),
+        }
+    }
+}
@@ -1,10 +1,29 @@
+mod record;
+mod replay;
+mod stream;
+mod tcp;
 mod unix;
 
-use std::sync::Arc;
+use std::{
+    fmt,
+    os::unix::io::{FromRawFd, RawFd},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use crate::Result;
+use futures::io::{AsyncRead, AsyncWrite};
+
+use crate::{runtime, Error, Result};
+pub use record::{Direction, RecordTransport, RecordedFrame, Recording};
+pub use replay::ReplayTransport;
+pub use stream::StreamTransport;
+use tcp::TcpTransport;
 use unix::UnixTransport;
 
+pub(crate) const DEFAULT_TCP_KEEPALIVE_MS: u64 = 30 * 1000;
+pub(crate) const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 3 * 1000;
+
 #[async_trait::async_trait]
 pub trait Transport: Sync + Send {
     async fn write(&self, buf: &[u8]) -> Result<()>;
@@ -15,4 +34,109 @@ pub async fn unix(sock: &str) -> Result<Arc<dyn Transport>> {
     Ok(Arc::new(UnixTransport::new(sock).await?))
 }
 
-// TODO: Add tcp
+/// Api socket forwarded over tcp, e.g. by socat, with default keepalive and connect timeout
+pub async fn tcp(addr: &str) -> Result<Arc<dyn Transport>> {
+    tcp_with_keepalive(addr, Some(DEFAULT_TCP_KEEPALIVE_MS)).await
+}
+
+/// Like `tcp`, keepalive is the idle time in milliseconds before probes, None to disable
+pub async fn tcp_with_keepalive(addr: &str, keepalive: Option<u64>) -> Result<Arc<dyn Transport>> {
+    tcp_with_timeout(addr, keepalive, DEFAULT_CONNECT_TIMEOUT_MS).await
+}
+
+/// Like `tcp_with_keepalive`, connect fails with `Error::Timeout` after timeout milliseconds
+pub async fn tcp_with_timeout(
+    addr: &str,
+    keepalive: Option<u64>,
+    timeout: u64,
+) -> Result<Arc<dyn Transport>> {
+    let trans = runtime::timeout(
+        Duration::from_millis(timeout),
+        TcpTransport::new(addr, keepalive),
+    )
+    .await
+    .unwrap_or_else(|| Err(Error::timeout(format!("Connect '{}' timeout", addr))))?;
+
+    Ok(Arc::new(trans))
+}
+
+/// Connected unix socket, e.g. from socket activation, must be called inside runtime
+pub fn unix_from_std(stream: std::os::unix::net::UnixStream) -> Result<Arc<dyn Transport>> {
+    Ok(Arc::new(UnixTransport::from_std(stream)?))
+}
+
+/// Like `unix_from_std`, takes ownership of fd
+///
+/// # Safety
+///
+/// fd must be an open unix stream socket not owned by anything else.
+pub unsafe fn unix_from_raw_fd(fd: RawFd) -> Result<Arc<dyn Transport>> {
+    unix_from_std(std::os::unix::net::UnixStream::from_raw_fd(fd))
+}
+
+/// Any stream implementing `futures::io::AsyncRead` and `AsyncWrite`
+pub fn stream<S>(stream: S) -> Arc<dyn Transport>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    Arc::new(StreamTransport::new(stream))
+}
+
+/// Wrap transport to write every frame sent and received to file
+pub fn record<P: AsRef<Path>>(inner: Arc<dyn Transport>, path: P) -> Result<Arc<dyn Transport>> {
+    Ok(Arc::new(RecordTransport::new(inner, path)?))
+}
+
+/// Serve a file written by `record`, client must send the same sequence of requests
+pub fn replay<P: AsRef<Path>>(path: P) -> Result<Arc<dyn Transport>> {
+    Ok(Arc::new(ReplayTransport::load(path)?))
+}
+
+/// Where client connects to, kept for reconnecting
+#[derive(Clone)]
+pub(crate) enum Endpoint {
+    Unix(String),
+    Tcp {
+        addr: String,
+        keepalive: Option<u64>,
+        timeout: u64,
+    },
+    /// Transport given by user, used once and never reopened
+    Custom(Arc<Mutex<Option<Arc<dyn Transport>>>>),
+}
+
+impl Endpoint {
+    pub fn custom(transport: Arc<dyn Transport>) -> Self {
+        Endpoint::Custom(Arc::new(Mutex::new(Some(transport))))
+    }
+
+    pub fn can_reconnect(&self) -> bool {
+        !matches!(self, Endpoint::Custom(_))
+    }
+
+    pub async fn connect(&self) -> Result<Arc<dyn Transport>> {
+        match self {
+            Endpoint::Unix(sock) => unix(sock).await,
+            Endpoint::Tcp {
+                addr,
+                keepalive,
+                timeout,
+            } => tcp_with_timeout(addr, *keepalive, *timeout).await,
+            Endpoint::Custom(transport) => transport
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .ok_or(Error::disconnected("Custom transport can't be reopened")),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Unix(sock) => write!(f, "unix:{}", sock),
+            Endpoint::Tcp { addr, .. } => write!(f, "tcp:{}", addr),
+            Endpoint::Custom(_) => write!(f, "custom"),
+        }
+    }
+}
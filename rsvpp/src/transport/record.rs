@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::Transport;
+use crate::{
+    hard_coded_message::{
+        VlApiSockclntCreateReplyT, VlApiSockclntCreateT, VL_API_SOCK_CLNT_CREATE_MSG_ID,
+        VL_API_SOCK_CLNT_CREATE_REP_MSG_ID,
+    },
+    message::{MessageHeader, MessageName},
+    pack::Pack,
+    Error, Result,
+};
+
+const RECORDING_HEADER: &str = "# rsvpp recording v1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Written by client
+    Send,
+    /// Read by client
+    Recv,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Send => write!(f, "tx"),
+            Direction::Recv => write!(f, "rx"),
+        }
+    }
+}
+
+/// One frame of a recording, `data` includes the frame header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+    pub direction: Direction,
+    /// Milliseconds since unix epoch
+    pub timestamp: u64,
+    pub msg_id: u16,
+    /// Name from the message table of the session, `-` if unknown
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl RecordedFrame {
+    fn parse(line: &str) -> Result<Self> {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            return Err(Error::argument(format!("Invalid recorded frame: {}", line)));
+        }
+
+        let direction = match fields[0] {
+            "tx" => Direction::Send,
+            "rx" => Direction::Recv,
+            dir => return Err(Error::argument(format!("Invalid direction: {}", dir))),
+        };
+        let timestamp = fields[1]
+            .parse()
+            .map_err(|e| Error::argument(format!("Invalid timestamp {}: {}", fields[1], e)))?;
+        let msg_id = fields[2]
+            .parse()
+            .map_err(|e| Error::argument(format!("Invalid message id {}: {}", fields[2], e)))?;
+
+        Ok(Self {
+            direction,
+            timestamp,
+            msg_id,
+            name: fields[3].to_string(),
+            data: decode_hex(fields[4])?,
+        })
+    }
+}
+
+impl fmt::Display for RecordedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} ",
+            self.direction, self.timestamp, self.msg_id, self.name
+        )?;
+        self.data.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Frames of a session in order, stored as one line of text per frame
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let mut frames = Vec::new();
+        for line in file.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            frames.push(RecordedFrame::parse(line)?);
+        }
+
+        Ok(Self { frames })
+    }
+
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    pub(super) fn into_frames(self) -> Vec<RecordedFrame> {
+        self.frames
+    }
+}
+
+/// Collect frames from a byte stream which may be split or batched arbitrarily
+#[derive(Default)]
+pub(super) struct FrameSplitter {
+    buf: Vec<u8>,
+}
+
+impl FrameSplitter {
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buf.extend_from_slice(data);
+
+        let header_size = MessageHeader::static_size();
+        let mut frames = Vec::new();
+        while self.buf.len() >= header_size {
+            let frame_size = header_size + MessageHeader::decode(&self.buf)?.len as usize;
+            if self.buf.len() < frame_size {
+                break;
+            }
+            frames.push(self.buf.drain(..frame_size).collect());
+        }
+
+        Ok(frames)
+    }
+}
+
+pub(super) fn frame_msg_id(frame: &[u8]) -> u16 {
+    frame
+        .get(MessageHeader::static_size()..)
+        .and_then(|payload| u16::unpack(payload, 0).ok())
+        .map(|(id, _)| id)
+        .unwrap_or_default()
+}
+
+struct RecordState {
+    file: BufWriter<File>,
+    names: HashMap<u16, String>,
+    send: FrameSplitter,
+    recv: FrameSplitter,
+}
+
+impl RecordState {
+    fn record(&mut self, direction: Direction, data: &[u8]) -> Result<()> {
+        let frames = match direction {
+            Direction::Send => self.send.push(data)?,
+            Direction::Recv => self.recv.push(data)?,
+        };
+
+        for data in frames {
+            let msg_id = frame_msg_id(&data);
+            if msg_id == VL_API_SOCK_CLNT_CREATE_REP_MSG_ID && direction == Direction::Recv {
+                self.learn_names(&data);
+            }
+
+            let frame = RecordedFrame {
+                direction,
+                timestamp: now_ms(),
+                msg_id,
+                name: self.name(direction, msg_id),
+                data,
+            };
+            writeln!(self.file, "{}", frame)?;
+        }
+
+        // Keep the file usable if the process dies
+        self.file.flush()?;
+
+        Ok(())
+    }
+
+    fn name(&self, direction: Direction, msg_id: u16) -> String {
+        match (direction, msg_id) {
+            (Direction::Send, VL_API_SOCK_CLNT_CREATE_MSG_ID) => {
+                VlApiSockclntCreateT::message_name().to_string()
+            }
+            (Direction::Recv, VL_API_SOCK_CLNT_CREATE_REP_MSG_ID) => {
+                VlApiSockclntCreateReplyT::message_name().to_string()
+            }
+            _ => self
+                .names
+                .get(&msg_id)
+                .cloned()
+                .unwrap_or_else(|| "-".to_string()),
+        }
+    }
+
+    /// Message table is taken from the sockclnt_create reply seen on the wire
+    fn learn_names(&mut self, frame: &[u8]) {
+        let reply =
+            match VlApiSockclntCreateReplyT::unpack(&frame[MessageHeader::static_size()..], 0) {
+                Ok((reply, _)) => reply,
+                Err(e) => {
+                    log::warn!("Recorder can't decode message table: {}", e);
+                    return;
+                }
+            };
+
+        self.names = reply
+            .message_table
+            .into_iter()
+            .map(|entry| {
+                let name = match entry.name.rfind('_') {
+                    Some(pos) => entry.name[..pos].to_string(),
+                    None => entry.name,
+                };
+                (entry.index, name)
+            })
+            .collect();
+    }
+}
+
+/// Transport writing every frame passing through it to a recording file
+pub struct RecordTransport {
+    inner: Arc<dyn Transport>,
+    state: Mutex<RecordState>,
+}
+
+impl RecordTransport {
+    /// File is truncated, frames are appended as they're read or written
+    pub fn new<P: AsRef<Path>>(inner: Arc<dyn Transport>, path: P) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", RECORDING_HEADER)?;
+
+        Ok(Self {
+            inner,
+            state: Mutex::new(RecordState {
+                file,
+                names: HashMap::new(),
+                send: FrameSplitter::default(),
+                recv: FrameSplitter::default(),
+            }),
+        })
+    }
+
+    fn record(&self, direction: Direction, data: &[u8]) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = state.record(direction, data) {
+            log::error!("Record {} frame error: {}", direction, e);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for RecordTransport {
+    async fn write(&self, buf: &[u8]) -> Result<()> {
+        // Record before write, the reply may be read before write returns
+        self.record(Direction::Send, buf);
+        self.inner.write(buf).await
+    }
+
+    async fn read(&self, nbytes: usize) -> Result<Vec<u8>> {
+        let buf = self.inner.read(nbytes).await?;
+        self.record(Direction::Recv, &buf);
+
+        Ok(buf)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(Error::argument("Invalid hex data"));
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16)
+                .map_err(|e| Error::argument(format!("Invalid hex data: {}", e)))
+        })
+        .collect()
+}
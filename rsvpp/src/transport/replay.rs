@@ -0,0 +1,135 @@
+use std::{path::Path, sync::Mutex};
+
+use super::{
+    record::{frame_msg_id, Direction, FrameSplitter, RecordedFrame, Recording},
+    Transport,
+};
+use crate::{signal::Signal, Error, Result};
+
+struct ReplayState {
+    frames: Vec<RecordedFrame>,
+    /// Index of next frame expected from client
+    send_pos: usize,
+    /// Index of next frame served to client
+    recv_pos: usize,
+    /// Bytes of served frames not read yet
+    recv_buf: Vec<u8>,
+    send: FrameSplitter,
+}
+
+impl ReplayState {
+    /// Received frame is served once every frame client sent before it is written
+    fn next_recv(&mut self) -> Option<Vec<u8>> {
+        while self.recv_pos < self.frames.len() {
+            let frame = &self.frames[self.recv_pos];
+            if frame.direction == Direction::Send {
+                if self.recv_pos >= self.send_pos {
+                    return None;
+                }
+                self.recv_pos += 1;
+                continue;
+            }
+
+            self.recv_pos += 1;
+            return Some(frame.data.clone());
+        }
+
+        None
+    }
+
+    fn expect_send(&mut self, frame: &[u8]) -> Result<()> {
+        let msg_id = frame_msg_id(frame);
+        while self.send_pos < self.frames.len() {
+            let expected = &self.frames[self.send_pos];
+            self.send_pos += 1;
+            if expected.direction == Direction::Recv {
+                continue;
+            }
+
+            if expected.msg_id != msg_id {
+                return Err(Error::internal(format!(
+                    "Replay diverged, expect message {} ({}), got {}",
+                    expected.msg_id, expected.name, msg_id
+                )));
+            }
+
+            return Ok(());
+        }
+
+        Err(Error::internal(format!(
+            "Replay diverged, unexpected message {} after end of recording",
+            msg_id
+        )))
+    }
+}
+
+/// Transport serving a recording back to client
+///
+/// Received frames are delivered in recorded order, each one after the frames client
+/// sent before it in the recording have been written again. Written frames are checked
+/// by message id only. Reads wait like an idle vpp once the recording is exhausted.
+pub struct ReplayTransport {
+    state: Mutex<ReplayState>,
+    signal: Signal,
+}
+
+impl ReplayTransport {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            state: Mutex::new(ReplayState {
+                frames: recording.into_frames(),
+                send_pos: 0,
+                recv_pos: 0,
+                recv_buf: Vec::new(),
+                send: FrameSplitter::default(),
+            }),
+            signal: Signal::new(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(Recording::load(path)?))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ReplayState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for ReplayTransport {
+    async fn write(&self, buf: &[u8]) -> Result<()> {
+        {
+            let mut state = self.lock();
+            for frame in state.send.push(buf)? {
+                state.expect_send(&frame)?;
+            }
+        }
+
+        // Wake up reader waiting for this request
+        self.signal.send();
+
+        Ok(())
+    }
+
+    async fn read(&self, nbytes: usize) -> Result<Vec<u8>> {
+        let mut listener = self.signal.subscribe();
+        loop {
+            {
+                let mut state = self.lock();
+                while state.recv_buf.len() < nbytes {
+                    match state.next_recv() {
+                        Some(frame) => state.recv_buf.extend(frame),
+                        None => break,
+                    }
+                }
+
+                if state.recv_buf.len() >= nbytes {
+                    return Ok(state.recv_buf.drain(..nbytes).collect());
+                }
+            }
+
+            listener.recv().await;
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use super::Transport;
+use crate::Result;
+use futures::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    lock::Mutex,
+};
+
+/// Transport over any `futures::io` stream, e.g. tokio streams wrapped by `tokio_util::compat`
+pub struct StreamTransport<S> {
+    rd: Mutex<ReadHalf<S>>,
+    wr: Mutex<WriteHalf<S>>,
+}
+
+impl<S> StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    pub fn new(stream: S) -> Self {
+        let (rd, wr) = stream.split();
+
+        Self {
+            rd: Mutex::new(rd),
+            wr: Mutex::new(wr),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> Transport for StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    async fn write(&self, buf: &[u8]) -> Result<()> {
+        let mut wr = self.wr.lock().await;
+        wr.write_all(buf).await?;
+        wr.flush().await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, nbytes: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0_u8; nbytes];
+        self.rd.lock().await.read_exact(&mut buf).await?;
+
+        Ok(buf)
+    }
+}
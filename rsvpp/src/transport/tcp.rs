@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use super::Transport;
+use crate::{
+    runtime::{self, StreamReader, StreamWriter},
+    Result,
+};
+use futures::lock::Mutex;
+
+pub struct TcpTransport {
+    rd: Mutex<StreamReader>,
+    wr: Mutex<StreamWriter>,
+}
+
+impl TcpTransport {
+    /// Connect to `host:port`, keepalive is the idle time in milliseconds before probes
+    pub async fn new(addr: &str, keepalive: Option<u64>) -> Result<Self> {
+        // Create tcp stream, split to owned read & write
+        let keepalive = keepalive.map(Duration::from_millis);
+        let (rd, wr) = runtime::connect_tcp(addr, keepalive).await?;
+
+        Ok(Self {
+            rd: Mutex::new(rd),
+            wr: Mutex::new(wr),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for TcpTransport {
+    async fn write(&self, buf: &[u8]) -> Result<()> {
+        self.wr.lock().await.write_all(buf).await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, nbytes: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0_u8; nbytes];
+        self.rd.lock().await.read_exact(&mut buf).await?;
+
+        Ok(buf)
+    }
+}
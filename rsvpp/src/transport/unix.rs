@@ -1,26 +1,29 @@
 use super::Transport;
-use crate::Result;
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::{
-        unix::{OwnedReadHalf, OwnedWriteHalf},
-        UnixStream,
-    },
-    sync::Mutex,
+use crate::{
+    runtime::{self, StreamReader, StreamWriter},
+    Result,
 };
+use futures::lock::Mutex;
 
 pub struct UnixTransport {
-    rd: Mutex<OwnedReadHalf>,
-    wr: Mutex<OwnedWriteHalf>,
+    rd: Mutex<StreamReader>,
+    wr: Mutex<StreamWriter>,
 }
 
 impl UnixTransport {
     pub async fn new(sock: &str) -> Result<Self> {
-        // Create unix stream
-        let stream = UnixStream::connect(sock).await?;
+        // Create unix stream, split to owned read & write
+        let (rd, wr) = runtime::connect_unix(sock).await?;
 
-        // Split stream to owned read & write
-        let (rd, wr) = stream.into_split();
+        Ok(Self {
+            rd: Mutex::new(rd),
+            wr: Mutex::new(wr),
+        })
+    }
+
+    /// Use connected stream, e.g. received by fd passing
+    pub fn from_std(stream: std::os::unix::net::UnixStream) -> Result<Self> {
+        let (rd, wr) = runtime::unix_from_std(stream)?;
 
         Ok(Self {
             rd: Mutex::new(rd),
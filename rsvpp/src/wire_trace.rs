@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Write},
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    hard_coded_message::{
+        VlApiSockclntCreateReplyT, VlApiSockclntCreateT, VL_API_SOCK_CLNT_CREATE_MSG_ID,
+        VL_API_SOCK_CLNT_CREATE_REP_MSG_ID,
+    },
+    message::{MessageHeader, MessageName},
+    pack::Pack,
+};
+
+const LOG_TARGET: &str = "rsvpp::wire";
+const MAX_TRACKED_REQUESTS: usize = 4096;
+const TRACKED_REQUEST_TTL: Duration = Duration::from_secs(60);
+
+type DecodeFn = fn(&[u8]) -> Option<String>;
+
+/// Log every frame sent and received, with message names resolved from the message table
+///
+/// Enabled by `ClientBuilder::wire_trace`, lines are logged to target `rsvpp::wire`.
+#[derive(Debug)]
+pub struct WireTrace {
+    level: log::Level,
+    hexdump: bool,
+    decoders: HashMap<String, DecodeFn>,
+    names: RwLock<HashMap<u16, String>>,
+    sent: Mutex<HashMap<u32, Instant>>,
+}
+
+impl Default for WireTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WireTrace {
+    pub fn new() -> Self {
+        Self {
+            level: log::Level::Debug,
+            hexdump: false,
+            decoders: HashMap::new(),
+            names: RwLock::new(HashMap::new()),
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Log level of trace lines, `Debug` by default
+    pub fn level(mut self, level: log::Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Append hexdump of whole frame to each line
+    pub fn hexdump(mut self, enable: bool) -> Self {
+        self.hexdump = enable;
+        self
+    }
+
+    /// Pretty print payload of message `T`, see `register_trace` of generated code
+    pub fn decode<T: Pack + MessageName + Debug>(mut self) -> Self {
+        self.decoders.insert(
+            T::message_name().to_string(),
+            decode_payload::<T> as DecodeFn,
+        );
+        self
+    }
+
+    pub(crate) fn set_names<I: IntoIterator<Item = (u16, String)>>(&self, names: I) {
+        *self.names.write().unwrap_or_else(|e| e.into_inner()) = names.into_iter().collect();
+    }
+
+    /// Trace buffer written to transport, may contain several frames
+    pub(crate) fn send(&self, buf: &[u8]) {
+        if !log::log_enabled!(target: LOG_TARGET, self.level) {
+            return;
+        }
+
+        let header_size = MessageHeader::static_size();
+        let mut rest = buf;
+        while rest.len() >= header_size {
+            let len = match MessageHeader::decode(rest) {
+                Ok(header) => header.len as usize,
+                Err(_) => break,
+            };
+            let frame = &rest[..(header_size + len).min(rest.len())];
+            rest = &rest[frame.len()..];
+
+            let payload = &frame[header_size..];
+            let msg_id = read_u16(payload, 0).unwrap_or_default();
+            let name = self.name(msg_id, true);
+
+            // Replies sent by client, e.g. keepalive, put context right after the id
+            let ctx_offset = if msg_id == VL_API_SOCK_CLNT_CREATE_MSG_ID || name.ends_with("_reply")
+            {
+                2
+            } else {
+                6
+            };
+            let ctx = read_u32(payload, ctx_offset).unwrap_or_default();
+            let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+            if sent.len() >= MAX_TRACKED_REQUESTS {
+                sent.retain(|_, time| time.elapsed() < TRACKED_REQUEST_TTL);
+            }
+            sent.insert(ctx, Instant::now());
+            drop(sent);
+
+            self.log("tx", &name, msg_id, ctx, frame, None);
+        }
+    }
+
+    /// Trace frame read from transport
+    pub(crate) fn recv(&self, header_buf: &[u8], payload: &[u8]) {
+        if !log::log_enabled!(target: LOG_TARGET, self.level) {
+            return;
+        }
+
+        let msg_id = read_u16(payload, 0).unwrap_or_default();
+        let ctx = read_u32(payload, 2).unwrap_or_default();
+        let name = self.name(msg_id, false);
+        let latency = self
+            .sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&ctx)
+            .map(Instant::elapsed);
+
+        let mut frame = header_buf.to_vec();
+        frame.extend_from_slice(payload);
+        self.log("rx", &name, msg_id, ctx, &frame, latency);
+    }
+
+    fn log(
+        &self,
+        dir: &str,
+        name: &str,
+        msg_id: u16,
+        ctx: u32,
+        frame: &[u8],
+        latency: Option<Duration>,
+    ) {
+        let payload = &frame[MessageHeader::static_size()..];
+        let mut line = format!(
+            "{} {}({}) ctx {} size {}",
+            dir,
+            name,
+            msg_id,
+            ctx,
+            payload.len()
+        );
+        if let Some(latency) = latency {
+            let _ = write!(line, " latency {:?}", latency);
+        }
+        if let Some(decoded) = self.decoders.get(name).and_then(|decode| decode(payload)) {
+            let _ = write!(line, "\n{}", decoded);
+        }
+        if self.hexdump {
+            line.push_str(&hexdump(frame));
+        }
+
+        log::log!(target: LOG_TARGET, self.level, "{}", line);
+    }
+
+    fn name(&self, msg_id: u16, sent: bool) -> String {
+        match (sent, msg_id) {
+            (true, VL_API_SOCK_CLNT_CREATE_MSG_ID) => {
+                VlApiSockclntCreateT::message_name().to_string()
+            }
+            (false, VL_API_SOCK_CLNT_CREATE_REP_MSG_ID) => {
+                VlApiSockclntCreateReplyT::message_name().to_string()
+            }
+            _ => self
+                .names
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&msg_id)
+                .cloned()
+                .unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+}
+
+fn decode_payload<T: Pack + Debug>(payload: &[u8]) -> Option<String> {
+    T::unpack(payload, 0)
+        .ok()
+        .map(|(msg, _)| format!("{:#?}", msg))
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    u16::unpack(buf.get(offset..)?, 0).ok().map(|(v, _)| v)
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    u32::unpack(buf.get(offset..)?, 0).ok().map(|(v, _)| v)
+}
+
+fn hexdump(buf: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in buf.chunks(16).enumerate() {
+        let _ = write!(out, "\n{:04x}:", i * 16);
+        chunk.iter().for_each(|b| {
+            let _ = write!(out, " {:02x}", b);
+        });
+    }
+
+    out
+}
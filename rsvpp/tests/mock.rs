@@ -0,0 +1,1207 @@
+#![cfg(feature = "testing")]
+
+use futures::StreamExt;
+use rsvpp::{
+    message::{
+        MessageClientId, MessageContext, MessageCrc, MessageDesc, MessageId, MessageName, Request,
+    },
+    pack::{self, Pack},
+    testing::{block_on, sleep, MockVpp},
+    Client,
+};
+
+macro_rules! message {
+    ($name:ident, $msg:expr, $crc:expr, { $($(#[$attr:meta])* $field:ident: $ty:ty),* }) => {
+        #[derive(Pack, Debug, Default, PartialEq)]
+        #[packed]
+        pub struct $name {
+            pub _vl_msg_id: u16,
+            $($(#[$attr])* pub $field: $ty),*
+        }
+
+        impl MessageName for $name {
+            fn message_name() -> &'static str {
+                $msg
+            }
+        }
+
+        impl MessageCrc for $name {
+            fn crc() -> &'static str {
+                $crc
+            }
+        }
+
+        impl MessageId for $name {
+            fn message_id(&self) -> u16 {
+                self._vl_msg_id
+            }
+
+            fn set_message_id(mut self, id: u16) -> Self {
+                self._vl_msg_id = id;
+                self
+            }
+        }
+
+        impl MessageContext for $name {
+            fn context(&self) -> u32 {
+                self.context
+            }
+
+            fn set_context(mut self, ctx: u32) -> Self {
+                self.context = ctx;
+                self
+            }
+        }
+    };
+}
+
+macro_rules! request {
+    ($name:ident, $reply:ident, $stream:expr) => {
+        impl MessageClientId for $name {
+            fn client_index(&self) -> u32 {
+                self.client_index
+            }
+
+            fn set_client_index(mut self, idx: u32) -> Self {
+                self.client_index = idx;
+                self
+            }
+        }
+
+        impl Request for $name {
+            type Reply = $reply;
+            const IS_STREAM: bool = $stream;
+        }
+    };
+}
+
+message!(ShowVersion, "show_version", "51077d14", { client_index: u32, context: u32 });
+message!(ShowVersionReply, "show_version_reply", "c919bde1", { context: u32, retval: i32, version: u32 });
+request!(ShowVersion, ShowVersionReply, false);
+
+message!(IfDump, "if_dump", "f9e6675e", { client_index: u32, context: u32, first: u32 });
+message!(IfDetails, "if_details", "6c221fc7", { context: u32, index: u32 });
+request!(IfDump, IfDetails, true);
+
+message!(ControlPing, "control_ping", "51077d14", { client_index: u32, context: u32 });
+message!(ControlPingReply, "control_ping_reply", "f6b0b8ca", { context: u32, retval: i32, client_index: u32, vpe_pid: u32 });
+request!(ControlPing, ControlPingReply, false);
+
+message!(SetFlag, "set_flag", "3c1e6a4b", { client_index: u32, context: u32, flag: u32 });
+message!(SetFlagReply, "set_flag_reply", "e8d4e804", { context: u32, retval: i32 });
+
+impl MessageClientId for SetFlag {
+    fn client_index(&self) -> u32 {
+        self.client_index
+    }
+
+    fn set_client_index(mut self, idx: u32) -> Self {
+        self.client_index = idx;
+        self
+    }
+}
+
+impl Request for SetFlag {
+    type Reply = SetFlagReply;
+    const IS_STREAM: bool = false;
+
+    fn check_reply(reply: &SetFlagReply) -> rsvpp::Result<()> {
+        match reply.retval {
+            0 => Ok(()),
+            retval => Err(rsvpp::Error::vpp_api(retval, None, "set_flag")),
+        }
+    }
+}
+
+message!(SetTag, "set_tag", "2f7d1a0c", { client_index: u32, context: u32, #[len(4)] tag: String });
+message!(SetTagReply, "set_tag_reply", "5a2c9e1b", { context: u32, retval: i32, len: u32 });
+request!(SetTag, SetTagReply, false);
+
+message!(IfEvent, "if_event", "58e39b2e", { client_index: u32, context: u32, index: u32 });
+
+message!(Keepalive, "memclnt_keepalive", "51077d14", { client_index: u32, context: u32 });
+message!(KeepaliveReply, "memclnt_keepalive_reply", "e8d4e804", { context: u32, retval: i32 });
+
+message!(SockclntCreate, "sockclnt_create", "455fb9c4", { context: u32, #[len(64)] name: String });
+
+message!(WantIfEvents, "want_if_events", "476f5a08", { client_index: u32, context: u32, enable: u32 });
+message!(WantIfEventsReply, "want_if_events_reply", "e8d4e804", { context: u32, retval: i32 });
+request!(WantIfEvents, WantIfEventsReply, false);
+
+#[test]
+fn call_scripted_reply() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2110,
+            ..Default::default()
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let reply = client.call(ShowVersion::default()).await.unwrap();
+        assert_eq!(reply.version, 2110);
+
+        let requests = mock.received_of::<ShowVersion>().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].context, reply.context);
+    })
+}
+
+#[test]
+fn dump_terminated_by_control_ping() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on_dump(|req: IfDump| {
+            (req.first..req.first + 3)
+                .map(|index| IfDetails {
+                    index,
+                    ..Default::default()
+                })
+                .collect()
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let details = client
+            .dump::<_, IfDetails>(IfDump {
+                first: 5,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .map(|details| details.unwrap().index)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(details, vec![5, 6, 7]);
+
+        let names = mock
+            .received()
+            .into_iter()
+            .map(|msg| msg.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["sockclnt_create", "if_dump", "control_ping"]);
+    })
+}
+
+#[test]
+fn unhandled_request_times_out() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.add_message(ShowVersion::message_name(), ShowVersion::crc());
+        mock.add_message(ShowVersionReply::message_name(), ShowVersionReply::crc());
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        client.set_timeout(100);
+        assert!(matches!(
+            client.call(ShowVersion::default()).await,
+            Err(rsvpp::Error::Timeout(_))
+        ));
+        assert_eq!(mock.received_of::<ShowVersion>().unwrap().len(), 1);
+    })
+}
+
+#[test]
+fn event_pushed_to_subscriber() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.add_message(IfEvent::message_name(), IfEvent::crc());
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let mut events = client.subscribe::<IfEvent>().await.unwrap();
+        mock.send_event(IfEvent {
+            index: 3,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert_eq!(events.next().await.unwrap().index, 3);
+    })
+}
+
+#[test]
+fn compatibility_against_message_table() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.add_message(ShowVersion::message_name(), ShowVersion::crc());
+        mock.add_message(SetFlag::message_name(), "deadbeef");
+        mock.add_module("acl", 2, 0, 1);
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let table = client.message_table();
+        let entry = |name: &str| table.iter().find(|entry| entry.name() == name).cloned();
+        assert_eq!(entry("show_version").unwrap().crc(), ShowVersion::crc());
+        assert_eq!(entry("set_flag").unwrap().crc(), "deadbeef");
+        assert!(entry("if_dump").is_none());
+        assert!(table.windows(2).all(|w| w[0].id() < w[1].id()));
+
+        assert!(client.supports::<ShowVersion>());
+        assert!(!client.supports::<SetFlag>());
+        assert!(!client.supports::<IfDump>());
+
+        assert!(client.has_plugin("acl").await.unwrap());
+        assert!(!client.has_plugin("nat44").await.unwrap());
+
+        let desc = |name, crc| MessageDesc { name, crc };
+        let report = client.compatibility_report(&[
+            desc(ShowVersion::message_name(), ShowVersion::crc()),
+            desc(SetFlag::message_name(), SetFlag::crc()),
+            desc(IfDump::message_name(), IfDump::crc()),
+        ]);
+        assert!(!report.is_compatible());
+        assert_eq!(
+            report.missing,
+            vec![desc(IfDump::message_name(), IfDump::crc())]
+        );
+        assert_eq!(
+            report.crc_mismatch,
+            vec![rsvpp::CrcMismatch {
+                name: "set_flag",
+                expected: SetFlag::crc(),
+                actual: "deadbeef".to_string(),
+            }]
+        );
+        assert!(client
+            .compatibility_report(&[desc(ShowVersion::message_name(), ShowVersion::crc())])
+            .is_compatible());
+    })
+}
+
+#[test]
+fn deadline_bounds_whole_dump() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on_dump(|_: IfDump| Vec::<IfDetails>::new());
+        // Keep the dump open, details are sent below one by one
+        mock.on(|_: ControlPing| {
+            std::thread::sleep(std::time::Duration::from_millis(1500));
+            ControlPingReply::default()
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        client.set_timeout(400);
+        let started = std::time::Instant::now();
+        let deadline = started + std::time::Duration::from_millis(600);
+        let dump = client
+            .dump_with::<_, IfDetails>(IfDump::default(), rsvpp::CallOptions::deadline(deadline))
+            .await
+            .unwrap();
+
+        // Each detail is well within the client timeout, all of them are not within the deadline
+        while mock.received_of::<IfDump>().unwrap().is_empty() {
+            sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let ctx = mock.received_of::<IfDump>().unwrap()[0].context;
+        let send_details = async {
+            for index in 0..10 {
+                sleep(std::time::Duration::from_millis(100)).await;
+                let _ = mock.send_event(IfDetails {
+                    context: ctx,
+                    index,
+                    ..Default::default()
+                });
+            }
+        };
+        let collect = async {
+            let details = dump.collect::<Vec<_>>().await;
+            (details, started.elapsed())
+        };
+        let ((details, elapsed), _) = futures::future::join(collect, send_details).await;
+
+        let (last, received) = details.split_last().unwrap();
+        assert!(matches!(last, Err(rsvpp::Error::Timeout(_))));
+        assert!(!received.is_empty() && received.len() < 9);
+        assert!(received.iter().all(Result::is_ok));
+        assert!(elapsed < std::time::Duration::from_millis(1000));
+    })
+}
+
+#[test]
+fn dropped_call_discards_late_reply() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        mock.on(move |_: ShowVersion| {
+            let version = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if version == 1 {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+            }
+            ShowVersionReply {
+                version,
+                ..Default::default()
+            }
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let call = Box::pin(client.call(ShowVersion::default()));
+        let timer = Box::pin(sleep(std::time::Duration::from_millis(100)));
+        assert!(matches!(
+            futures::future::select(call, timer).await,
+            futures::future::Either::Right(_)
+        ));
+
+        // Dropped future discards its ctx in background
+        sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(client.metrics().await.session.outstanding_contexts, 0);
+
+        // Mock replies in order, the late reply of the dropped call comes first
+        assert_eq!(
+            client.call(ShowVersion::default()).await.unwrap().version,
+            2
+        );
+        let requests = mock.received_of::<ShowVersion>().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_ne!(requests[0].context, requests[1].context);
+
+        let session = client.metrics().await.session;
+        assert_eq!(session.outstanding_contexts, 0);
+        assert_eq!(session.cache_contexts, 0);
+    })
+}
+
+#[test]
+fn client_name_sent_in_sockclnt_create() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+
+        let name = "n".repeat(63);
+        let client = Client::builder()
+            .socket(mock.socket())
+            .name(&name)
+            .connect()
+            .await
+            .unwrap();
+        let creates = mock.received_of::<SockclntCreate>().unwrap();
+        assert_eq!(creates.len(), 1);
+        assert_eq!(creates[0].name, name);
+        client.close().await.unwrap();
+
+        for name in &["".to_string(), "n".repeat(64)] {
+            assert!(matches!(
+                Client::builder()
+                    .socket(mock.socket())
+                    .name(name)
+                    .connect()
+                    .await,
+                Err(rsvpp::Error::Argument(_))
+            ));
+        }
+        assert_eq!(mock.received_of::<SockclntCreate>().unwrap().len(), 1);
+    })
+}
+
+#[test]
+fn socket_discovered_from_env() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2402,
+            ..Default::default()
+        });
+
+        // No other test reads the env
+        std::env::set_var("VPP_API_SOCKET", mock.socket());
+        assert_eq!(
+            rsvpp::ClientBuilder::discover_socket().unwrap(),
+            mock.socket()
+        );
+        let client = Client::builder().connect().await.unwrap();
+        std::env::remove_var("VPP_API_SOCKET");
+
+        assert_eq!(
+            client.call(ShowVersion::default()).await.unwrap().version,
+            2402
+        );
+    })
+}
+
+#[test]
+fn replay_recorded_session() {
+    block_on(async {
+        let path = std::env::temp_dir().join(format!("rsvpp-replay-{}.rec", std::process::id()));
+
+        // Record
+        {
+            let mock = MockVpp::start().unwrap();
+            mock.on(|_: ShowVersion| ShowVersionReply {
+                version: 2206,
+                ..Default::default()
+            });
+
+            let transport = rsvpp::transport::unix(mock.socket()).await.unwrap();
+            let transport = rsvpp::transport::record(transport, &path).unwrap();
+            let client = Client::connect_with_transport(transport).await.unwrap();
+            client.call(ShowVersion::default()).await.unwrap();
+            client.close().await.unwrap();
+        }
+
+        let recording = rsvpp::transport::Recording::load(&path).unwrap();
+        let names = recording
+            .frames()
+            .iter()
+            .map(|frame| frame.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "sockclnt_create",
+                "sockclnt_create_reply",
+                "show_version",
+                "show_version_reply",
+                "sockclnt_delete",
+                "sockclnt_delete_reply"
+            ]
+        );
+
+        // Replay without vpp
+        let transport = rsvpp::transport::replay(&path).unwrap();
+        let client = Client::connect_with_transport(transport).await.unwrap();
+        let reply = client.call(ShowVersion::default()).await.unwrap();
+        assert_eq!(reply.version, 2206);
+        client.close().await.unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    })
+}
+
+struct WireLogger(std::sync::Mutex<Vec<String>>);
+
+impl log::Log for WireLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "rsvpp::wire"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static WIRE_LOGGER: WireLogger = WireLogger(std::sync::Mutex::new(Vec::new()));
+
+#[test]
+fn wire_trace_decodes_registered_types() {
+    block_on(async {
+        log::set_logger(&WIRE_LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Debug);
+
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2302,
+            ..Default::default()
+        });
+
+        let trace = rsvpp::WireTrace::new()
+            .hexdump(true)
+            .decode::<ShowVersionReply>();
+        let client = Client::builder()
+            .socket(mock.socket())
+            .wire_trace(trace)
+            .connect()
+            .await
+            .unwrap();
+        client.call(ShowVersion::default()).await.unwrap();
+
+        let lines = WIRE_LOGGER.0.lock().unwrap().clone();
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("tx show_version(")));
+        let reply = lines
+            .iter()
+            .find(|line| line.starts_with("rx show_version_reply("))
+            .unwrap();
+        assert!(reply.contains("latency"));
+        assert!(reply.contains("version: 2302"));
+        assert!(reply.contains("\n0000:"));
+    })
+}
+
+#[test]
+fn metrics_count_outcomes() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|req: SetFlag| SetFlagReply {
+            retval: if req.flag == 0 { -1 } else { 0 },
+            ..Default::default()
+        });
+        mock.on_dump(|_: IfDump| vec![IfDetails::default()]);
+        mock.add_message(ShowVersion::message_name(), ShowVersion::crc());
+        mock.add_message(ShowVersionReply::message_name(), ShowVersionReply::crc());
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        client
+            .call(SetFlag {
+                flag: 1,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        client.call(SetFlag::default()).await.unwrap_err();
+        client.set_timeout(50);
+        client.call(ShowVersion::default()).await.unwrap_err();
+        client
+            .dump::<_, IfDetails>(IfDump::default())
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        let metrics = client.metrics().await;
+        let set_flag = &metrics.messages["set_flag"];
+        assert_eq!(set_flag.requests, 2);
+        assert_eq!(set_flag.retval_errors, 1);
+        assert_eq!(set_flag.latency.count, 2);
+        assert!(set_flag.latency.quantile(0.5).is_some());
+
+        let show_version = &metrics.messages["show_version"];
+        assert_eq!(show_version.requests, 1);
+        assert_eq!(show_version.timeouts, 1);
+        assert_eq!(show_version.latency.count, 0);
+
+        assert_eq!(metrics.messages["if_dump"].latency.count, 1);
+    })
+}
+
+#[test]
+fn retry_failed_attempts() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        let attempts = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = attempts.clone();
+        mock.on(move |_: SetFlag| {
+            let attempt = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            SetFlagReply {
+                retval: if attempt == 0 { -1 } else { 0 },
+                ..Default::default()
+            }
+        });
+
+        let policy = rsvpp::RetryPolicy::new(3)
+            .backoff(10, 10)
+            .retry_if(|e| matches!(e, rsvpp::Error::VppApi { code: -1, .. }));
+        let client = Client::builder()
+            .socket(mock.socket())
+            .retry_policy(policy)
+            .connect()
+            .await
+            .unwrap();
+
+        // Not idempotent, client policy doesn't apply
+        client.call(SetFlag::default()).await.unwrap_err();
+        attempts.store(0, std::sync::atomic::Ordering::SeqCst);
+
+        client
+            .call_with(SetFlag::default(), rsvpp::CallOptions::retry(policy))
+            .await
+            .unwrap();
+
+        let requests = mock.received_of::<SetFlag>().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_ne!(requests[1].context, requests[2].context);
+        assert_eq!(client.metrics().await.messages["set_flag"].requests, 3);
+    })
+}
+
+#[test]
+fn send_many_keeps_order_on_encode_error() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|req: SetTag| SetTagReply {
+            len: req.tag.len() as u32,
+            ..Default::default()
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let tags = || {
+            ["a", "bb", "too long", "ccc", "dd", "e"]
+                .iter()
+                .map(|tag| SetTag {
+                    tag: tag.to_string(),
+                    ..Default::default()
+                })
+        };
+        let lens = |replies: Vec<rsvpp::Result<SetTagReply>>| {
+            replies
+                .into_iter()
+                .map(|rep| rep.map(|rep| rep.len).map_err(|e| e.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // The failed request gets its own slot and the rest are still sent
+        let opts = rsvpp::BatchOptions {
+            window: 4,
+            error_mode: rsvpp::BatchErrorMode::CollectAll,
+        };
+        let replies = lens(client.send_many(tags(), opts).await);
+        assert_eq!(replies.len(), 6);
+        assert_eq!(replies[..2], [Ok(1), Ok(2)]);
+        assert!(replies[2].as_ref().unwrap_err().starts_with("Encode error"));
+        assert_eq!(replies[3..], [Ok(3), Ok(2), Ok(1)]);
+
+        // Requests in flight are still received after the failed one
+        let opts = rsvpp::BatchOptions {
+            window: 4,
+            error_mode: rsvpp::BatchErrorMode::StopOnError,
+        };
+        let replies = lens(client.send_many(tags(), opts).await);
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[..2], [Ok(1), Ok(2)]);
+        assert!(replies[2].is_err());
+        assert_eq!(mock.received_of::<SetTag>().unwrap().len(), 5 + 2);
+    })
+}
+
+#[test]
+fn event_not_taken_as_reply() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.add_message(ShowVersion::message_name(), ShowVersion::crc());
+        mock.add_message(ShowVersionReply::message_name(), ShowVersionReply::crc());
+        mock.add_message(IfEvent::message_name(), IfEvent::crc());
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        client.set_timeout(100);
+        let mut events = client.subscribe::<IfEvent>().await.unwrap();
+
+        // Context slot of the event holds `client_index`, equal to ctx of the pending request
+        let ctx = client.send_msg(ShowVersion::default()).await.unwrap();
+        mock.send_event(IfEvent {
+            client_index: ctx,
+            index: 4,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(matches!(
+            client.recv_msg::<ShowVersionReply>(ctx).await,
+            Err(rsvpp::Error::Timeout(_))
+        ));
+        assert_eq!(events.next().await.unwrap().index, 4);
+    })
+}
+
+#[test]
+fn finished_dump_releases_context() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on_dump(|_: IfDump| vec![IfDetails::default(), IfDetails::default()]);
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        for _ in 0..5 {
+            let details = client
+                .dump::<_, IfDetails>(IfDump::default())
+                .await
+                .unwrap()
+                .collect::<Vec<_>>()
+                .await;
+            assert_eq!(details.len(), 2);
+        }
+
+        assert_eq!(client.metrics().await.session.outstanding_contexts, 0);
+    })
+}
+
+#[test]
+fn health_check_reconnects_and_enables_events() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: WantIfEvents| WantIfEventsReply::default());
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2402,
+            ..Default::default()
+        });
+        mock.add_message(IfEvent::message_name(), IfEvent::crc());
+
+        let client = Client::builder()
+            .socket(mock.socket())
+            .reconnect_policy(rsvpp::ReconnectPolicy {
+                interval: 50,
+                ..Default::default()
+            })
+            .health_check(rsvpp::HealthCheck {
+                interval: 50,
+                timeout: 100,
+                max_failures: 1,
+            })
+            .connect()
+            .await
+            .unwrap();
+        let (mut events, _) = client
+            .subscribe_with::<IfEvent, _>(WantIfEvents {
+                enable: 1,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // Reconnected by health check without any request
+        mock.disconnect_all();
+        for _ in 0..40 {
+            if mock.received_of::<WantIfEvents>().unwrap().len() == 2 {
+                break;
+            }
+            sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        let creates = mock
+            .received()
+            .into_iter()
+            .filter(|msg| msg.name == "sockclnt_create")
+            .count();
+        assert_eq!(creates, 2);
+        let enables = mock.received_of::<WantIfEvents>().unwrap();
+        assert_eq!(enables.len(), 2);
+        assert_eq!(enables[1].enable, 1);
+        assert_ne!(enables[0].context, enables[1].context);
+
+        mock.send_event(IfEvent {
+            index: 9,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(events.next().await.unwrap().index, 9);
+        assert_eq!(
+            client.call(ShowVersion::default()).await.unwrap().version,
+            2402
+        );
+    })
+}
+
+#[test]
+fn send_with_ctx_requires_free_reserved_ctx() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.add_message(ShowVersion::message_name(), ShowVersion::crc());
+        mock.add_message(ShowVersionReply::message_name(), ShowVersionReply::crc());
+
+        let client = Client::builder()
+            .socket(mock.socket())
+            .reserve_contexts(100..=109)
+            .connect()
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.send_msg_with_ctx(ShowVersion::default(), 1).await,
+            Err(rsvpp::Error::Argument(_))
+        ));
+        assert_eq!(
+            client
+                .send_msg_with_ctx(ShowVersion::default(), 105)
+                .await
+                .unwrap(),
+            105
+        );
+
+        // Still waiting for reply
+        assert!(matches!(
+            client.send_msg_with_ctx(ShowVersion::default(), 105).await,
+            Err(rsvpp::Error::Argument(_))
+        ));
+        while mock.received_of::<ShowVersion>().unwrap().is_empty() {
+            sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(mock.received_of::<ShowVersion>().unwrap().len(), 1);
+    })
+}
+
+#[test]
+fn send_with_ctx_of_sent_dump() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on_dump(|_: IfDump| {
+            vec![IfDetails {
+                index: 3,
+                ..Default::default()
+            }]
+        });
+
+        // Dump terminated by hand like generated code did before `Client::dump`
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let ctx = client.send_msg(IfDump::default()).await.unwrap();
+        assert_eq!(
+            client
+                .send_msg_with_ctx(ControlPing::default(), ctx)
+                .await
+                .unwrap(),
+            ctx
+        );
+
+        let mut indexes = Vec::new();
+        'outer: loop {
+            for entry in client.recv(ctx).await.unwrap() {
+                if entry.header._vl_msg_id == client.get_msg_id::<ControlPingReply>().unwrap() {
+                    break 'outer;
+                }
+                indexes.push(IfDetails::unpack(&entry.data, 0).unwrap().0.index);
+            }
+        }
+        assert_eq!(indexes, vec![3]);
+
+        let pings = mock.received_of::<ControlPing>().unwrap();
+        assert_eq!(pings.len(), 1);
+        assert_eq!(pings[0].context, ctx);
+    })
+}
+
+#[test]
+fn keepalive_answered() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply::default());
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        mock.send_event(Keepalive {
+            context: 77,
+            ..Default::default()
+        })
+        .unwrap();
+
+        // Answered by the recv task, not left in cache
+        client.call(ShowVersion::default()).await.unwrap();
+        let mut replies = Vec::new();
+        for _ in 0..20 {
+            replies = mock.received_of::<KeepaliveReply>().unwrap();
+            if !replies.is_empty() {
+                break;
+            }
+            sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(replies.len(), 1);
+        assert_eq!((replies[0].context, replies[0].retval), (77, 0));
+        assert_eq!(client.metrics().await.session.cache_messages, 0);
+    })
+}
+
+#[test]
+fn pool_replaces_failed_member() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2406,
+            ..Default::default()
+        });
+
+        let pool = rsvpp::ClientPool::new(mock.socket(), 2).await.unwrap();
+        let first = pool.get().await.unwrap();
+        let second = pool.get().await.unwrap();
+        assert!(!std::sync::Arc::ptr_eq(&first, &second));
+        assert!(std::sync::Arc::ptr_eq(&pool.get().await.unwrap(), &first));
+
+        // Closed member is unhealthy, replaced by a new client when selected
+        first.close().await.unwrap();
+        assert!(std::sync::Arc::ptr_eq(&pool.get().await.unwrap(), &second));
+        let replaced = pool.get().await.unwrap();
+        assert!(!std::sync::Arc::ptr_eq(&replaced, &first));
+        assert!(replaced.is_healthy());
+        assert_eq!(
+            replaced.call(ShowVersion::default()).await.unwrap().version,
+            2406
+        );
+
+        // Shared pool switches strategy, idle members tie and the first is selected
+        pool.set_strategy(rsvpp::PoolStrategy::LeastInFlight);
+        assert!(std::sync::Arc::ptr_eq(
+            &pool.get().await.unwrap(),
+            &replaced
+        ));
+        assert!(std::sync::Arc::ptr_eq(
+            &pool.get().await.unwrap(),
+            &replaced
+        ));
+
+        let creates = mock
+            .received()
+            .into_iter()
+            .filter(|msg| msg.name == "sockclnt_create")
+            .count();
+        assert_eq!(creates, 3);
+    })
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_client() {
+    let mock = MockVpp::start().unwrap();
+    mock.on(|_: ShowVersion| ShowVersionReply {
+        version: 2410,
+        ..Default::default()
+    });
+    mock.on_dump(|req: IfDump| {
+        (req.first..req.first + 2)
+            .map(|index| IfDetails {
+                index,
+                ..Default::default()
+            })
+            .collect()
+    });
+    mock.add_message(IfEvent::message_name(), IfEvent::crc());
+
+    let client = rsvpp::blocking::Client::connect_unix(mock.socket()).unwrap();
+    assert_eq!(client.call(ShowVersion::default()).unwrap().version, 2410);
+    let details: Vec<IfDetails> = client
+        .dump(IfDump {
+            first: 1,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(
+        details.iter().map(|d| d.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+
+    let mut events = client.subscribe::<IfEvent>().unwrap();
+    mock.send_event(IfEvent {
+        index: 6,
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(events.next().unwrap().index, 6);
+    drop(events);
+
+    // Dropping unregisters from vpp
+    drop(client);
+    assert_eq!(mock.received().last().unwrap().name, "sockclnt_delete");
+}
+
+#[test]
+fn close_waits_in_flight_calls() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            ShowVersionReply {
+                version: 2412,
+                ..Default::default()
+            }
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        let close = async {
+            while mock.received_of::<ShowVersion>().unwrap().is_empty() {
+                sleep(std::time::Duration::from_millis(10)).await;
+            }
+
+            client.close().await
+        };
+        let (rep, closed) = futures::future::join(client.call(ShowVersion::default()), close).await;
+        closed.unwrap();
+        assert_eq!(rep.unwrap().version, 2412);
+        assert!(matches!(
+            client.call(ShowVersion::default()).await,
+            Err(rsvpp::Error::Closed(_))
+        ));
+
+        let names = mock
+            .received()
+            .into_iter()
+            .map(|msg| msg.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["sockclnt_create", "show_version", "sockclnt_delete"]
+        );
+    })
+}
+
+/// Forward tcp connections on loopback to the unix socket of mock
+fn tcp_proxy(mock: &MockVpp) -> std::net::SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let path = mock.socket_path().to_path_buf();
+
+    std::thread::spawn(move || {
+        for tcp in listener.incoming() {
+            let (tcp, unix) = match (tcp, std::os::unix::net::UnixStream::connect(&path)) {
+                (Ok(tcp), Ok(unix)) => (tcp, unix),
+                _ => return,
+            };
+            let (mut tcp_rd, mut unix_wr) = (tcp.try_clone().unwrap(), unix.try_clone().unwrap());
+            let (mut unix_rd, mut tcp_wr) = (unix, tcp);
+            std::thread::spawn(move || {
+                let _ = std::io::copy(&mut tcp_rd, &mut unix_wr);
+                let _ = unix_wr.shutdown(std::net::Shutdown::Both);
+            });
+            std::thread::spawn(move || {
+                let _ = std::io::copy(&mut unix_rd, &mut tcp_wr);
+                let _ = tcp_wr.shutdown(std::net::Shutdown::Both);
+            });
+        }
+    });
+
+    addr
+}
+
+#[test]
+fn connect_over_tcp() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2502,
+            ..Default::default()
+        });
+        mock.on_dump(|_: IfDump| vec![IfDetails::default(), IfDetails::default()]);
+
+        let client = Client::builder()
+            .tcp(tcp_proxy(&mock))
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(
+            client.call(ShowVersion::default()).await.unwrap().version,
+            2502
+        );
+        let details = client
+            .dump::<_, IfDetails>(IfDump::default())
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(details.len(), 2);
+        client.close().await.unwrap();
+
+        assert_eq!(mock.received().last().unwrap().name, "sockclnt_delete");
+    })
+}
+
+#[test]
+fn tcp_connect_timeout() {
+    block_on(async {
+        let connect = |addr: String| Client::builder().tcp(addr).connect_timeout(200).connect();
+
+        // Connected but never registered, timeout covers registering too
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let started = std::time::Instant::now();
+        let res = connect(silent.local_addr().unwrap().to_string()).await;
+        assert!(matches!(res, Err(rsvpp::Error::Timeout(_))));
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+        // Non-routable, connecting hangs until timeout unless the network is intercepted
+        let addr = "10.255.255.1:5002";
+        let probe = std::net::TcpStream::connect_timeout(
+            &addr.parse().unwrap(),
+            std::time::Duration::from_millis(100),
+        );
+        if !matches!(probe, Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut) {
+            eprintln!("Skip connecting {}, probe result: {:?}", addr, probe);
+            return;
+        }
+
+        let started = std::time::Instant::now();
+        let res = connect(addr.to_string()).await;
+        assert!(matches!(res, Err(rsvpp::Error::Timeout(_))));
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    })
+}
+
+#[test]
+fn client_from_std_stream() {
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2506,
+            ..Default::default()
+        });
+
+        let stream = std::os::unix::net::UnixStream::connect(mock.socket_path()).unwrap();
+        let client = Client::from_std(stream).await.unwrap();
+        assert_eq!(
+            client.call(ShowVersion::default()).await.unwrap().version,
+            2506
+        );
+
+        // Given stream can't be reopened
+        mock.disconnect_all();
+        assert!(client.call(ShowVersion::default()).await.is_err());
+        assert_eq!(mock.received_of::<SockclntCreate>().unwrap().len(), 1);
+    })
+}
+
+/// Closed spans
+#[cfg(feature = "tracing")]
+#[derive(Clone, Default)]
+struct SpanRecorder(std::sync::Arc<std::sync::Mutex<Vec<(String, SpanFields)>>>);
+
+/// Fields of span formatted by `Debug`
+#[cfg(feature = "tracing")]
+#[derive(Clone, Default)]
+struct SpanFields(Vec<(String, String)>);
+
+#[cfg(feature = "tracing")]
+impl tracing::field::Visit for SpanFields {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<S> tracing_subscriber::Layer<S> for SpanRecorder
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fn on_new_span(
+        &self,
+        attrs: &tracing::span::Attributes<'_>,
+        id: &tracing::span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(
+        &self,
+        id: &tracing::span::Id,
+        values: &tracing::span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let span = ctx.span(id).unwrap();
+        values.record(span.extensions_mut().get_mut::<SpanFields>().unwrap());
+    }
+
+    fn on_close(&self, id: tracing::span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let fields = span.extensions().get::<SpanFields>().unwrap().clone();
+        self.0
+            .lock()
+            .unwrap()
+            .push((span.name().to_string(), fields));
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn call_recorded_in_span() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let recorder = SpanRecorder::default();
+    let subscriber = tracing_subscriber::registry().with(recorder.clone());
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    block_on(async {
+        let mock = MockVpp::start().unwrap();
+        mock.on(|_: ShowVersion| ShowVersionReply {
+            version: 2510,
+            ..Default::default()
+        });
+
+        let client = Client::connect_unix(mock.socket()).await.unwrap();
+        client.call(ShowVersion::default()).await.unwrap();
+        let ctx = mock.received_of::<ShowVersion>().unwrap()[0].context;
+
+        let spans = recorder.0.lock().unwrap().clone();
+        let (_, fields) = spans.iter().find(|(name, _)| name == "rsvpp.call").unwrap();
+        let field = |name: &str| {
+            fields
+                .0
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(field("message").unwrap(), "show_version");
+        assert_eq!(field("ctx").unwrap(), ctx.to_string());
+        assert_eq!(field("retval").unwrap(), "0");
+    })
+}